@@ -1,9 +1,30 @@
 // src/app.rs
 
-use crate::db::NoteClient;
-use ratatui::widgets::ListState;
+use crate::config::LayoutSettings;
+use crate::db::{NoteClient, SortOrder};
+use crate::export::Format;
+use crate::highlight::DEFAULT_THEME;
+use crate::keymap::Keymap;
+use crate::markdown;
+use crate::models::{
+    note::Note,
+    notebook::{self, TreeEntry},
+    search::SearchHit,
+};
+use crate::text_buffer::TextBuffer;
+use std::cell::{OnceCell, Ref, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::rc::Rc;
 use uuid::Uuid;
 
+/// Maximum number of notes shown while a search filter is active
+pub const SEARCH_LIMIT: u32 = 200;
+
+/// Notes read from the database at a time while paging through the unfiltered list
+pub const PAGE_SIZE: u32 = 200;
+
 pub enum Mode {
     List,
     AddTitle,
@@ -12,30 +33,394 @@ pub enum Mode {
     EditContent,
 }
 
+/// An unchecked checklist item with the note it is in
+#[derive(Clone)]
+pub struct OpenTask {
+    pub note: Note,
+    /// Position among all the tasks of the note, done or not
+    pub index: usize,
+    pub text: String,
+}
+
+// What the list screen shows for one filter, valid until the client writes again
+struct Cache {
+    generation: u64,
+    query: Option<String>,
+    tag: Option<String>,
+    favorites: bool,
+    notebook: Option<Uuid>,
+    sort: SortOrder,
+    hits: Rc<Vec<SearchHit>>,
+    // the filtered notes, or the pages of all notes read so far
+    notes: Vec<Note>,
+    // notes in the list, read or not
+    total: usize,
+    // more pages can be read, which is the case without filters
+    paged: bool,
+    tags: Rc<Vec<(String, usize)>>,
+    notebooks: Rc<Vec<TreeEntry>>,
+    titles: Rc<HashSet<String>>,
+    // read on first use, as only the open tasks view needs them
+    open_tasks: OnceCell<Rc<Vec<OpenTask>>>,
+    // done and total checklist items of the notes shown so far
+    task_counts: RefCell<HashMap<Uuid, (usize, usize)>>,
+}
+
 pub struct App {
     pub selected: usize,
-    pub state: ListState,
     pub mode: Mode,
-    pub input: String,
+    pub input: TextBuffer,
     pub buffer: String,
+    pub query: Option<String>,
+    pub tag: Option<String>,
+    /// Only list notes marked as favorites
+    pub favorites: bool,
+    /// Only list notes in this notebook and the notebooks below it
+    pub notebook: Option<Uuid>,
     pub edit_id: Option<Uuid>,
     pub delete_id: Option<Uuid>,
+    /// A note just renamed and its old title, while links to that title can be updated
+    pub renamed: Option<(Uuid, String)>,
+    /// The note just moved to the trash, while its deletion can still be undone
+    pub last_trashed: Option<Uuid>,
+    /// syntect theme for code blocks in the preview
+    pub theme: String,
+    /// Where and how the export key writes notes
+    pub export_dir: PathBuf,
+    pub export_format: Format,
+    /// A one-off message for the list title, cleared by the next key
+    pub status: Option<String>,
+    /// Set after another program used the screen, which then has to be repainted
+    pub redraw: bool,
+    pub keymap: Keymap,
+    /// Edit with vim's modes instead of plain typing
+    pub vim: bool,
+    /// Whether and how the list screen shows a preview pane
+    pub layout: LayoutSettings,
+    /// Order of the list while no search is active
+    pub sort: SortOrder,
+    /// The settings file, where the chosen sort order is saved
+    pub settings_path: PathBuf,
     pub note_client: NoteClient,
+    // filled on first use, so renders and key presses do not query the database
+    cache: RefCell<Option<Cache>>,
 }
 
 impl App {
     pub fn new(note_client: NoteClient) -> Self {
-        let mut ls = ListState::default();
-        ls.select(Some(0));
         App {
             selected: 0,
-            state: ls,
             mode: Mode::List,
-            input: String::new(),
+            input: TextBuffer::new(),
             buffer: String::new(),
+            query: None,
+            tag: None,
+            favorites: false,
+            notebook: None,
             edit_id: None,
             delete_id: None,
+            renamed: None,
+            last_trashed: None,
+            theme: DEFAULT_THEME.to_string(),
+            export_dir: PathBuf::from("export"),
+            export_format: Format::Markdown,
+            status: None,
+            redraw: false,
+            keymap: Keymap::default(),
+            vim: false,
+            layout: LayoutSettings::default(),
+            sort: SortOrder::default(),
+            settings_path: PathBuf::from("Settings.toml"),
             note_client,
+            cache: RefCell::new(None),
+        }
+    }
+
+    /// The search query, if one is set and not blank
+    pub fn active_query(&self) -> Option<&str> {
+        self.query.as_deref().filter(|q| !q.trim().is_empty())
+    }
+
+    pub fn search_hits(&self) -> Rc<Vec<SearchHit>> {
+        Rc::clone(&self.cached(0).hits)
+    }
+
+    /// Number of notes listed, honoring the search and tag filters
+    pub fn note_count(&self) -> usize {
+        self.cached(0).total
+    }
+
+    /// The listed notes at positions `range`, in display order
+    pub fn notes_in(&self, range: Range<usize>) -> Vec<Note> {
+        let cache = self.cached(range.end);
+        let end = range.end.min(cache.notes.len());
+        cache.notes[range.start.min(end)..end].to_vec()
+    }
+
+    /// The highlighted note of the list
+    pub fn selected_note(&self) -> Option<Note> {
+        self.cached(self.selected + 1)
+            .notes
+            .get(self.selected)
+            .cloned()
+    }
+
+    /// Moves the selection to the note with `id`, reading pages until it turns up
+    pub fn select(&mut self, id: Uuid) {
+        let mut upto = PAGE_SIZE as usize;
+        loop {
+            let cache = self.cached(upto);
+            if let Some(index) = cache.notes.iter().position(|n| n.id == id) {
+                drop(cache);
+                self.selected = index;
+                return;
+            }
+            if cache.notes.len() < upto {
+                return;
+            }
+            upto += PAGE_SIZE as usize;
+        }
+    }
+
+    /// Tags of notes outside the trash with their note counts, by name
+    pub fn tags_with_counts(&self) -> Rc<Vec<(String, usize)>> {
+        Rc::clone(&self.cached(0).tags)
+    }
+
+    /// Every notebook in tree order with its note count
+    pub fn notebook_tree(&self) -> Rc<Vec<TreeEntry>> {
+        Rc::clone(&self.cached(0).notebooks)
+    }
+
+    /// Lowercased titles of the notes outside the trash, for telling missing links apart
+    pub fn link_titles(&self) -> Rc<HashSet<String>> {
+        Rc::clone(&self.cached(0).titles)
+    }
+
+    /// The unchecked items of every note outside the trash, most recently updated notes
+    /// first
+    pub fn open_tasks(&self) -> Rc<Vec<OpenTask>> {
+        let cache = self.cached(0);
+        let tasks = cache.open_tasks.get_or_init(|| {
+            let notes = self.note_client.notes_with_open_tasks().unwrap_or_default();
+            let tasks = notes.into_iter().flat_map(|note| {
+                markdown::tasks(&note.content)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, task)| !task.done)
+                    .map(|(index, task)| OpenTask {
+                        note: note.clone(),
+                        index,
+                        text: task.text,
+                    })
+                    .collect::<Vec<_>>()
+            });
+            Rc::new(tasks.collect())
+        });
+        Rc::clone(tasks)
+    }
+
+    /// How many of a note's checklist items are done and how many it has, worked out once
+    /// per note until the next write
+    pub fn task_counts(&self, note: &Note) -> (usize, usize) {
+        let cache = self.cached(0);
+        let mut counts = cache.task_counts.borrow_mut();
+        *counts
+            .entry(note.id)
+            .or_insert_with(|| markdown::task_counts(&note.content))
+    }
+
+    // The cache holding at least the first `upto` notes of the list, reloaded first
+    // when a write or a new filter made it stale
+    fn cached(&self, upto: usize) -> Ref<'_, Cache> {
+        let query = self.active_query().map(str::to_string);
+        {
+            let mut slot = self.cache.borrow_mut();
+            let stale = slot.as_ref().is_none_or(|cache| {
+                cache.generation != self.note_client.generation()
+                    || cache.query != query
+                    || cache.tag != self.tag
+                    || cache.favorites != self.favorites
+                    || cache.notebook != self.notebook
+                    || cache.sort != self.sort
+            });
+            if stale {
+                *slot = Some(self.load(query));
+            }
+            let cache = slot.as_mut().unwrap();
+            while cache.paged && cache.notes.len() < upto.min(cache.total) {
+                let page = self
+                    .note_client
+                    .get_notes_paginated(self.sort, cache.notes.last(), PAGE_SIZE)
+                    .unwrap_or_default();
+                if page.is_empty() {
+                    break;
+                }
+                cache.notes.extend(page);
+            }
+        }
+        Ref::map(self.cache.borrow(), |cache| cache.as_ref().unwrap())
+    }
+
+    // Filtered lists are read whole; the full list only gets counted, its pages come later
+    fn load(&self, query: Option<String>) -> Cache {
+        let client = &self.note_client;
+        let notebooks = client.notebook_tree().unwrap_or_default();
+        // the chosen notebook with the notebooks below it
+        let scope: Option<Vec<Uuid>> = self.notebook.map(|id| {
+            notebooks
+                .iter()
+                .position(|e| e.notebook.id == id)
+                .map(|index| notebook::subtree(&notebooks, index))
+                .unwrap_or_default()
+        });
+        let keep = |note: &Note| {
+            self.tag.as_ref().is_none_or(|tag| note.tags.contains(tag))
+                && (!self.favorites || note.favorite)
+                && scope
+                    .as_ref()
+                    .is_none_or(|ids| note.notebook_id.is_some_and(|id| ids.contains(&id)))
+        };
+        let mut hits = match &query {
+            Some(q) => client.search(q, SEARCH_LIMIT).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let notes = if query.is_some() {
+            hits.retain(|h| keep(&h.note));
+            Some(hits.iter().map(|h| h.note.clone()).collect())
+        } else if let Some(tag) = &self.tag {
+            Some(client.notes_with_tag(tag, self.sort).unwrap_or_default())
+        } else if self.favorites {
+            Some(client.favorite_notes(self.sort).unwrap_or_default())
+        } else {
+            scope.as_ref().map(|ids| {
+                client
+                    .notes_in_notebooks(ids, self.sort)
+                    .unwrap_or_default()
+            })
+        };
+        let (notes, total, paged) = match notes {
+            Some(mut notes) => {
+                notes.retain(|n| keep(n));
+                let total = notes.len();
+                (notes, total, false)
+            }
+            None => (Vec::new(), client.count_notes().unwrap_or_default(), true),
+        };
+        Cache {
+            generation: client.generation(),
+            query,
+            tag: self.tag.clone(),
+            favorites: self.favorites,
+            notebook: self.notebook,
+            sort: self.sort,
+            hits: Rc::new(hits),
+            notes,
+            total,
+            paged,
+            tags: Rc::new(client.all_tags_with_counts().unwrap_or_default()),
+            notebooks: Rc::new(notebooks),
+            titles: Rc::new(client.link_titles().unwrap_or_default()),
+            open_tasks: OnceCell::new(),
+            task_counts: RefCell::new(HashMap::new()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_cache_follows_writes_and_filters() {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        client.add_note(&Note::new("One", "first #a")).unwrap();
+        let mut app = App::new(client);
+        let tags = app.tags_with_counts();
+        assert_eq!(app.note_count(), 1);
+        // no write, no reload
+        assert!(Rc::ptr_eq(&tags, &app.tags_with_counts()));
+
+        let two = Note::new("Two", "second #b");
+        app.note_client.add_note(&two).unwrap();
+        assert_eq!(app.note_count(), 2);
+        assert_eq!(app.tags_with_counts().len(), 2);
+
+        app.tag = Some("b".to_string());
+        assert_eq!(app.selected_note().map(|n| n.id), Some(two.id));
+        app.tag = None;
+        app.query = Some("first".to_string());
+        assert_eq!(app.search_hits().len(), 1);
+
+        app.note_client.trash_note(two.id).unwrap();
+        app.query = None;
+        assert_eq!(app.note_count(), 1);
+    }
+
+    #[test]
+    fn test_pages_are_read_as_needed() {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        let count = PAGE_SIZE as usize * 2 + 5;
+        for i in 0..count {
+            client.add_note(&Note::new(&format!("{}", i), "")).unwrap();
+        }
+        let mut app = App::new(client);
+        assert_eq!(app.note_count(), count);
+        assert_eq!(app.cache.borrow().as_ref().unwrap().notes.len(), 0);
+
+        assert_eq!(app.notes_in(10..20).len(), 10);
+        assert_eq!(app.cache.borrow().as_ref().unwrap().notes.len(), 200);
+        app.selected = count - 1;
+        assert!(app.selected_note().is_some());
+        assert_eq!(app.notes_in(count - 3..count + 10).len(), 3);
+        assert_eq!(
+            app.notes_in(0..count),
+            app.note_client.get_all_notes().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pinned_notes_lead_and_favorites_filter() {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        let old = Note::new("Old", "#a");
+        client.add_note(&old).unwrap();
+        for i in 0..PAGE_SIZE + 3 {
+            client.add_note(&Note::new(&format!("{}", i), "")).unwrap();
+        }
+        let mut app = App::new(client);
+        app.note_client.set_pinned(old.id, true).unwrap();
+        app.select(old.id);
+        assert_eq!(app.selected, 0);
+        app.note_client.set_pinned(old.id, false).unwrap();
+        app.select(old.id);
+        assert_eq!(app.selected, PAGE_SIZE as usize + 3);
+
+        app.favorites = true;
+        app.selected = 0;
+        assert_eq!(app.note_count(), 0);
+        app.note_client.set_favorite(old.id, true).unwrap();
+        assert_eq!(app.note_count(), 1);
+        app.tag = Some("a".to_string());
+        assert_eq!(app.selected_note().map(|n| n.id), Some(old.id));
+    }
+
+    #[test]
+    fn test_open_tasks_are_cached_until_a_write() {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        let mut note = Note::new("Todo", "- [x] one\n- [ ] two\n- [ ] three");
+        client.add_note(&note).unwrap();
+        let app = App::new(client);
+        let tasks = app.open_tasks();
+        let items: Vec<(usize, &str)> = tasks.iter().map(|t| (t.index, t.text.as_str())).collect();
+        assert_eq!(items, [(1, "two"), (2, "three")]);
+        assert!(Rc::ptr_eq(&tasks, &app.open_tasks()));
+
+        assert_eq!(app.task_counts(&note), (1, 3));
+
+        note.content = "- [x] one\n- [x] two\n- [ ] three".to_string();
+        app.note_client.update_note(&mut note).unwrap();
+        assert_eq!(app.open_tasks().len(), 1);
+        assert_eq!(app.task_counts(&note), (2, 3));
+    }
+}
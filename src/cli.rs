@@ -0,0 +1,400 @@
+// src/cli.rs
+
+use crate::app::SEARCH_LIMIT;
+use crate::db::{NoteClient, SortOrder};
+use crate::editor;
+use crate::export::{self, Format};
+use crate::import::{self, Source};
+use crate::models::note::{Note, parse_tags};
+use crate::models::notebook::{self, TreeEntry};
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/// Notes in the terminal. Starts the interactive UI when no command is given.
+#[derive(Parser)]
+#[command(name = "terminote", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Add a note and print its id
+    Add {
+        #[arg(long)]
+        title: String,
+        /// Note text, or "-" to read it from stdin
+        #[arg(long)]
+        content: Option<String>,
+        /// Notebook path such as "Work/Projects", created when missing
+        #[arg(long)]
+        notebook: Option<String>,
+    },
+    /// List notes, newest first
+    List {
+        /// Only notes in this notebook and the notebooks below it
+        #[arg(long)]
+        notebook: Option<String>,
+    },
+    /// Print a note
+    Show {
+        /// Full id or a unique prefix of it
+        id: String,
+    },
+    /// Change the title or text of a note
+    Edit {
+        /// Full id or a unique prefix of it
+        id: String,
+        #[arg(long)]
+        title: Option<String>,
+        /// New text, or "-" to read it from stdin
+        #[arg(long, conflicts_with = "editor")]
+        content: Option<String>,
+        /// Change the text in $VISUAL or $EDITOR
+        #[arg(long)]
+        editor: bool,
+        /// Move the note to this notebook, created when missing; "" takes it out of notebooks
+        #[arg(long)]
+        notebook: Option<String>,
+    },
+    /// Move a note to the trash
+    Rm {
+        /// Full id or a unique prefix of it
+        id: String,
+    },
+    /// Full-text search, best matches first
+    Search { query: String },
+    /// Write every note to a directory of Markdown files, a JSON file or a tar archive
+    Export {
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: Format,
+        /// Directory for Markdown, file for the other formats
+        path: PathBuf,
+    },
+    /// Add notes from Markdown files, a terminote export or another app's export
+    Import {
+        #[arg(long, value_enum)]
+        from: Source,
+        path: PathBuf,
+    },
+    /// List, create or delete notebooks
+    Notebook {
+        #[command(subcommand)]
+        command: NotebookCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NotebookCommand {
+    /// Print the notebook tree with note counts
+    List,
+    /// Create a notebook, with any missing notebook above it
+    Add {
+        /// Path such as "Work/Projects"
+        path: String,
+    },
+    /// Delete a notebook and the notebooks below it; their notes stay outside notebooks
+    Rm { path: String },
+}
+
+pub fn run(
+    command: Command,
+    client: &NoteClient,
+    input: &mut impl Read,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Add {
+            title,
+            content,
+            notebook,
+        } => {
+            let content = match content {
+                Some(content) => read_content(content, input)?,
+                None => String::new(),
+            };
+            let mut note = Note::new(&title, &content);
+            if let Some(path) = notebook {
+                note.notebook_id = client.create_notebook_path(&path)?.map(|n| n.id);
+            }
+            client.add_note(&note)?;
+            writeln!(out, "{}", note.id)?;
+        }
+        Command::List { notebook } => {
+            let notes = match notebook {
+                Some(path) => {
+                    let tree = client.notebook_tree()?;
+                    let index = find_notebook(&tree, &path)?;
+                    client
+                        .notes_in_notebooks(&notebook::subtree(&tree, index), SortOrder::Created)?
+                }
+                None => client.get_all_notes()?,
+            };
+            for note in notes {
+                writeln!(out, "{}", summary(&note))?;
+            }
+        }
+        Command::Show { id } => {
+            let note = resolve(client, &id)?;
+            writeln!(out, "{}", note.title)?;
+            writeln!(out, "id:      {}", note.id)?;
+            writeln!(out, "created: {}", note.created_at.to_rfc3339())?;
+            writeln!(out, "updated: {}", note.updated_at.to_rfc3339())?;
+            if let Some(id) = note.notebook_id {
+                let tree = client.notebook_tree()?;
+                if let Some(entry) = tree.iter().find(|e| e.notebook.id == id) {
+                    writeln!(out, "notebook: {}", entry.path)?;
+                }
+            }
+            if let Some(deleted_at) = note.deleted_at {
+                writeln!(out, "trashed: {}", deleted_at.to_rfc3339())?;
+            }
+            let tags = client.tags_for(note.id)?;
+            if !tags.is_empty() {
+                writeln!(out, "tags:    {}", tags.join(" "))?;
+            }
+            writeln!(out)?;
+            write!(out, "{}", note.content)?;
+            if !note.content.ends_with('\n') {
+                writeln!(out)?;
+            }
+        }
+        Command::Edit {
+            id,
+            title,
+            content,
+            editor,
+            notebook,
+        } => {
+            if title.is_none() && content.is_none() && !editor && notebook.is_none() {
+                return Err(
+                    "nothing to change; pass --title, --content, --editor or --notebook".into(),
+                );
+            }
+            let mut note = resolve(client, &id)?;
+            if let Some(path) = notebook {
+                let notebook = client.create_notebook_path(&path)?;
+                client.set_notebook(note.id, notebook.map(|n| n.id))?;
+                if title.is_none() && content.is_none() && !editor {
+                    writeln!(out, "{}", note.id)?;
+                    return Ok(());
+                }
+            }
+            if let Some(title) = title {
+                note.title = title;
+            }
+            let content = match content {
+                Some(content) => Some(read_content(content, input)?),
+                None if editor => Some(editor::edit(&note.content)?),
+                None => None,
+            };
+            if let Some(content) = content {
+                note.content = content;
+                note.tags = parse_tags(&note.content);
+            }
+            client.update_note(&mut note)?;
+            writeln!(out, "{}", note.id)?;
+        }
+        Command::Rm { id } => {
+            let note = resolve(client, &id)?;
+            if client.trash_note(note.id)? == 0 {
+                return Err(format!("note {} is already in the trash", short_id(&note)).into());
+            }
+            writeln!(
+                out,
+                "moved {} \"{}\" to the trash",
+                short_id(&note),
+                note.title
+            )?;
+        }
+        Command::Search { query } => {
+            for hit in client.search(&query, SEARCH_LIMIT)? {
+                writeln!(out, "{}", summary(&hit.note))?;
+                let snippet: String = hit.segments().into_iter().map(|(s, _)| s).collect();
+                writeln!(out, "    {}", snippet.replace('\n', " ").trim())?;
+            }
+        }
+        Command::Export { format, path } => {
+            let notes = client.get_all_notes()?;
+            export::export(&notes, format, &path)?;
+            writeln!(out, "exported {} notes to {}", notes.len(), path.display())?;
+        }
+        Command::Import { from, path } => {
+            let summary = import::import(client, from, &path)?;
+            writeln!(
+                out,
+                "created {} notes, skipped {} already present",
+                summary.created, summary.skipped
+            )?;
+            for (file, error) in &summary.failed {
+                writeln!(out, "failed to read {}: {}", file.display(), error)?;
+            }
+        }
+        Command::Notebook { command } => run_notebook(command, client, out)?,
+    }
+    Ok(())
+}
+
+fn run_notebook(
+    command: NotebookCommand,
+    client: &NoteClient,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    match command {
+        NotebookCommand::List => {
+            for entry in client.notebook_tree()? {
+                writeln!(
+                    out,
+                    "{}{}  ({})",
+                    "  ".repeat(entry.depth),
+                    entry.notebook.name,
+                    entry.count
+                )?;
+            }
+        }
+        NotebookCommand::Add { path } => {
+            if client.create_notebook_path(&path)?.is_none() {
+                return Err("a notebook needs a name".into());
+            }
+        }
+        NotebookCommand::Rm { path } => {
+            let tree = client.notebook_tree()?;
+            let entry = &tree[find_notebook(&tree, &path)?];
+            client.delete_notebook(entry.notebook.id)?;
+            writeln!(out, "deleted notebook {}", entry.path)?;
+        }
+    }
+    Ok(())
+}
+
+// The position of the notebook at `path` in `tree`
+fn find_notebook(tree: &[TreeEntry], path: &str) -> Result<usize, Box<dyn Error>> {
+    notebook::find(tree, path).ok_or_else(|| format!("no notebook \"{}\"", path).into())
+}
+
+// "-" stands for standard input
+fn read_content(content: String, input: &mut impl Read) -> std::io::Result<String> {
+    if content != "-" {
+        return Ok(content);
+    }
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+fn short_id(note: &Note) -> String {
+    note.id.simple().to_string()[..8].to_string()
+}
+
+// One line per note: short id, creation date, title and tags
+fn summary(note: &Note) -> String {
+    let mut line = format!(
+        "{}  {}  {}",
+        short_id(note),
+        note.created_at.format("%Y-%m-%d"),
+        note.title
+    );
+    for tag in &note.tags {
+        line.push_str(" #");
+        line.push_str(tag);
+    }
+    line
+}
+
+/// Finds the note for a full id or a unique prefix, as printed by `list`
+fn resolve(client: &NoteClient, id: &str) -> Result<Note, Box<dyn Error>> {
+    let digits: String = id.chars().filter(|c| *c != '-').collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid note id \"{}\"", id).into());
+    }
+    let mut notes = client.notes_with_id_prefix(&digits)?;
+    match notes.len() {
+        0 => Err(format!("no note with id \"{}\"", id).into()),
+        1 => Ok(notes.remove(0)),
+        n => Err(format!("id \"{}\" matches {} notes; use more digits", id, n).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn exec(client: &NoteClient, args: &[&str], stdin: &str) -> Result<String, Box<dyn Error>> {
+        let cli = Cli::try_parse_from(std::iter::once("terminote").chain(args.iter().copied()))?;
+        let mut out = Vec::new();
+        run(
+            cli.command.unwrap(),
+            client,
+            &mut stdin.as_bytes(),
+            &mut out,
+        )?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn test_add_show_edit_rm() {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        let id = exec(
+            &client,
+            &["add", "--title", "Shopping", "--content", "-"],
+            "milk #errand",
+        )
+        .unwrap();
+        let prefix = &id.trim()[..6];
+
+        let listed = exec(&client, &["list"], "").unwrap();
+        assert!(listed.contains("Shopping #errand"));
+
+        exec(&client, &["edit", prefix, "--title", "Groceries"], "").unwrap();
+        let shown = exec(&client, &["show", id.trim()], "").unwrap();
+        assert!(shown.starts_with("Groceries\n"));
+        assert!(shown.contains("\ntags:    errand\n"));
+        assert!(shown.ends_with("milk #errand\n"));
+
+        let found = exec(&client, &["search", "milk"], "").unwrap();
+        assert!(found.contains("Groceries"));
+
+        exec(&client, &["rm", prefix], "").unwrap();
+        assert_eq!(exec(&client, &["list"], "").unwrap(), "");
+        assert!(exec(&client, &["rm", prefix], "").is_err());
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(exec(&client, &["show", "zz"], "").is_err());
+        assert!(exec(&client, &["show", "abc"], "").is_err());
+        assert!(exec(&client, &["edit", "abc"], "").is_err());
+    }
+
+    #[test]
+    fn test_notebooks() {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        let id = exec(
+            &client,
+            &["add", "--title", "Plan", "--notebook", "Work/Projects"],
+            "",
+        )
+        .unwrap();
+        exec(&client, &["add", "--title", "Loose"], "").unwrap();
+        exec(&client, &["notebook", "add", "Home"], "").unwrap();
+        assert_eq!(
+            exec(&client, &["notebook", "list"], "").unwrap(),
+            "Home  (0)\nWork  (1)\n  Projects  (1)\n"
+        );
+        let listed = exec(&client, &["list", "--notebook", "work"], "").unwrap();
+        assert!(listed.contains("Plan") && !listed.contains("Loose"));
+        assert!(exec(&client, &["list", "--notebook", "Nowhere"], "").is_err());
+
+        exec(&client, &["edit", id.trim(), "--notebook", "Home"], "").unwrap();
+        let shown = exec(&client, &["show", id.trim()], "").unwrap();
+        assert!(shown.contains("notebook: Home\n"));
+        exec(&client, &["notebook", "rm", "Home"], "").unwrap();
+        let shown = exec(&client, &["show", id.trim()], "").unwrap();
+        assert!(!shown.contains("notebook:"));
+    }
+}
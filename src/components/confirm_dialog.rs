@@ -2,15 +2,21 @@
 
 use crate::{app::App, components::component::Component, input::Action};
 use ratatui::{
-    layout::Rect,
-    widgets::{Block, Borders, Paragraph},
     Frame,
+    layout::{Margin, Position, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
 };
+use unicode_width::UnicodeWidthStr;
 
 pub struct ConfirmDialog {
     focus: bool,
     prompt: String,
     pub result: Option<bool>,
+    // where the buttons were drawn, for clicks
+    yes: Rect,
+    no: Rect,
 }
 
 impl ConfirmDialog {
@@ -19,9 +25,16 @@ impl ConfirmDialog {
             focus: false,
             prompt: prompt.into(),
             result: None,
+            yes: Rect::default(),
+            no: Rect::default(),
         }
     }
 
+    /// Replaces the question, for dialogs that ask about a particular note
+    pub fn set_prompt(&mut self, prompt: impl Into<String>) {
+        self.prompt = prompt.into();
+    }
+
     pub fn take_result(&mut self) -> Option<bool> {
         let r = self.result;
         self.result = None;
@@ -30,10 +43,27 @@ impl ConfirmDialog {
 }
 
 impl Component for ConfirmDialog {
-    fn render(&mut self, f: &mut Frame, area: Rect, _app: &App) {
-        let text = format!("{}\n\n(o = yes, x = no)", self.prompt);
+    fn render(&mut self, f: &mut Frame, area: Rect, app: &App) {
+        let yes = format!("[ Yes ({}) ]", app.keymap.key(Action::Yes));
+        let no = format!("[ No ({}) ]", app.keymap.key(Action::No));
+        let mut lines: Vec<Line> = self.prompt.lines().map(Line::raw).collect();
+        lines.push(Line::default());
+
+        // the buttons go on the row below the prompt and a blank line
+        let inner = area.inner(Margin::new(1, 1));
+        let y = inner.y.saturating_add(lines.len() as u16);
+        self.yes = Rect::new(inner.x, y, yes.width() as u16, 1).intersection(inner);
+        self.no = Rect::new(self.yes.right() + 3, y, no.width() as u16, 1).intersection(inner);
+
+        let button = Style::default().add_modifier(Modifier::REVERSED);
+        lines.push(Line::from(vec![
+            Span::styled(yes, button),
+            Span::raw("   "),
+            Span::styled(no, button),
+        ]));
 
-        let p = Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Confirm"));
+        let p =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Confirm"));
         f.render_widget(p, area);
     }
 
@@ -42,8 +72,16 @@ impl Component for ConfirmDialog {
             return;
         }
         match action {
-            Action::Char('o') => self.result = Some(true),
-            Action::Char('x') => self.result = Some(false),
+            Action::Yes => self.result = Some(true),
+            Action::No => self.result = Some(false),
+            Action::Click { column, row } => {
+                let at = Position::new(*column, *row);
+                if self.yes.contains(at) {
+                    self.result = Some(true);
+                } else if self.no.contains(at) {
+                    self.result = Some(false);
+                }
+            }
             _ => {}
         }
     }
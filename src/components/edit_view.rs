@@ -1,22 +1,34 @@
 // src/components/edit_view.rs
 
+use crate::text_buffer::{locate, offset_at, wrap_rows};
+use crate::vim::{Vim, VimMode};
 use crate::{
     app::{App, Mode},
     components::component::Component,
     input::Action,
 };
-use ratatui::widgets::Wrap;
 use ratatui::{
     Frame,
-    backend::Backend,
-    layout::{Position, Rect},
+    layout::{Margin, Position, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
 };
-use std::cmp::{max, min};
+
+/// Rows moved per scroll key press or wheel notch
+const SCROLL_STEP: usize = 3;
 
 pub struct EditView {
     focus: bool,
     scroll: usize,
+    // inner height at the last render, the distance of PageUp/PageDown
+    page: usize,
+    // where the last render drew, to map clicks onto the text
+    area: Rect,
+    // keep the cursor in view; off after an explicit scroll until the cursor moves
+    follow: bool,
+    // modal state, used when `App::vim` is on
+    vim: Vim,
 }
 
 impl EditView {
@@ -24,63 +36,154 @@ impl EditView {
         Self {
             focus: false,
             scroll: 0,
+            page: 1,
+            area: Rect::default(),
+            follow: true,
+            vim: Vim::new(),
         }
     }
 
-    fn compute_cursor(&self, area: Rect, input: &str) -> Position {
-        let width = area.width.saturating_sub(2) as usize;
-        let mut row = 0usize;
-        let mut col = 0usize;
+    /// True when Esc belongs to vim, e.g. to leave insert mode, instead of cancelling
+    pub fn captures_esc(&self, app: &App) -> bool {
+        app.vim && self.vim.captures_esc()
+    }
 
-        for ch in input.chars() {
-            if ch == '\n' {
-                row += 1;
-                col = 0;
+    // Hints for the block title, which in vim mode also names the current mode
+    fn title_hints(&self, app: &App) -> String {
+        let keys = &app.keymap;
+        let content = matches!(app.mode, Mode::AddContent | Mode::EditContent);
+        let save = if content {
+            format!(", {}=save", keys.key(Action::Save))
+        } else {
+            String::new()
+        };
+        if !app.vim {
+            let history = format!(
+                "{}/{}=undo/redo",
+                keys.key(Action::Undo),
+                keys.key(Action::Redo)
+            );
+            return if content {
+                format!("(↵=newline{}, {}, Esc=cancel)", save, history)
             } else {
-                // wrap if we'd exceed the inner width
-                if col >= width {
-                    row += 1;
-                    col = 0;
+                format!("({})", history)
+            };
+        }
+        let mode = self.vim.mode();
+        let hints = match mode {
+            VimMode::Normal => format!(
+                "i=insert, v=visual, u/{}=undo/redo{}, Esc=cancel",
+                keys.key(Action::Redo),
+                save
+            ),
+            VimMode::Insert => format!("Esc=normal mode{}", save),
+            VimMode::Visual | VimMode::VisualLine => "d=delete, y=yank, Esc=normal mode".into(),
+        };
+        format!("-- {} -- ({})", mode, hints)
+    }
+
+    // The wrapped rows of `text`, with a visual selection shown reversed
+    fn styled_rows<'a>(
+        &self,
+        app: &App,
+        text: &'a str,
+        rows: Vec<(usize, usize)>,
+    ) -> Vec<Line<'a>> {
+        let selection = if app.vim {
+            self.vim.selection(&app.input)
+        } else {
+            None
+        };
+        rows.into_iter()
+            .map(|(start, end)| match &selection {
+                Some(s) if s.start < end && s.end > start => {
+                    let (from, to) = (s.start.max(start), s.end.min(end));
+                    Line::from(vec![
+                        Span::raw(&text[start..from]),
+                        Span::styled(
+                            &text[from..to],
+                            Style::default().add_modifier(Modifier::REVERSED),
+                        ),
+                        Span::raw(&text[to..end]),
+                    ])
                 }
-                col += 1;
-            }
+                _ => Line::raw(&text[start..end]),
+            })
+            .collect()
+    }
+
+    // Screen position of the cursor at visual `row`/`col`, if it is scrolled into view
+    fn compute_cursor(&self, area: Rect, row: usize, col: usize) -> Option<Position> {
+        let height = area.height.saturating_sub(2) as usize;
+        let width = area.width.saturating_sub(2) as usize;
+        if row < self.scroll || row >= self.scroll + height {
+            return None;
         }
+        let x = area.x + 1 + col.min(width) as u16;
+        let y = area.y + 1 + (row - self.scroll) as u16;
+        Some(Position::new(x, y))
+    }
 
-        // clamp to the inner box
-        let max_rows = area.height.saturating_sub(2) as usize;
-        if row > max_rows {
-            row = max_rows;
+    // Moves the cursor to the clicked cell, if it is on the text
+    fn click(&mut self, column: u16, row: u16, app: &mut App) {
+        let inner = self.area.inner(Margin::new(1, 1));
+        if !inner.contains(Position::new(column, row)) {
+            return;
         }
-        let max_cols = width;
-        if col > max_cols {
-            col = max_cols;
+        let text = app.input.as_str();
+        let rows = wrap_rows(text, inner.width as usize);
+        let row = self.scroll + (row - inner.y) as usize;
+        let pos = offset_at(text, &rows, row, (column - inner.x) as usize);
+        app.input.set_cursor(pos);
+        if app.vim && self.vim.mode() != VimMode::Insert {
+            app.input.clamp_to_line();
         }
+    }
 
-        let x = area.x + 1 + col as u16;
-        let y = area.y + 1 + row as u16;
-        Position::new(x, y)
+    // Brings `row` into view when following the cursor, and keeps the view on the text
+    fn update_scroll(&mut self, row: usize, total: usize, height: usize) {
+        if self.follow {
+            if row < self.scroll {
+                self.scroll = row;
+            } else if row >= self.scroll + height {
+                self.scroll = row + 1 - height;
+            }
+        }
+        self.scroll = self.scroll.min(total.saturating_sub(height));
+    }
+
+    fn scroll_indicator(&self, total: usize, height: usize) -> String {
+        if total <= height {
+            "All".to_string()
+        } else if self.scroll == 0 {
+            "Top".to_string()
+        } else if self.scroll + height >= total {
+            "Bot".to_string()
+        } else {
+            format!("{}%", self.scroll * 100 / (total - height))
+        }
     }
 
     fn handle_enter(&mut self, app: &mut App) {
         match app.mode {
             Mode::AddTitle => self.handle_add_title(app),
-            Mode::AddContent | Mode::EditContent => app.input.push('\n'),
+            Mode::AddContent | Mode::EditContent => app.input.insert_char('\n'),
             Mode::EditTitle => self.handle_edit_title(app),
             _ => {}
         }
     }
 
     fn handle_add_title(&mut self, app: &mut App) {
-        app.buffer = app.input.clone();
+        app.buffer = app.input.as_str().to_string();
         app.input.clear();
         app.mode = Mode::AddContent;
     }
 
     fn handle_edit_title(&mut self, app: &mut App) {
-        app.buffer = app.input.clone();
+        app.buffer = app.input.as_str().to_string();
         if let Some(id) = app.edit_id {
             if let Ok(Some(n)) = app.note_client.get_note_by_id(id) {
-                app.input = n.content.clone();
+                app.input.set_text(n.content.clone());
             } else {
                 app.input.clear();
             }
@@ -93,25 +196,50 @@ impl EditView {
 
 impl Component for EditView {
     fn render(&mut self, f: &mut Frame<'_>, area: Rect, app: &App) {
-        let title = match app.mode {
+        let name = match app.mode {
             Mode::AddTitle => "New Title",
-            Mode::AddContent => "New Content (↵=newline, Ctrl+X=save, Esc=cancel)",
+            Mode::AddContent => "New Content",
             Mode::EditTitle => "Edit Title",
-            Mode::EditContent => "Edit Content (↵=newline, Ctrl+X=save, Esc=cancel)",
+            Mode::EditContent => "Edit Content",
             _ => unreachable!(),
         };
+        let title = format!("{} {}", name, self.title_hints(app));
 
-        let block = Block::default().borders(Borders::ALL).title(title);
+        // wrap by hand so the cursor can be placed exactly
+        let width = area.width.saturating_sub(2) as usize;
+        let height = area.height.saturating_sub(2).max(1) as usize;
+        let text = app.input.as_str();
+        let rows = wrap_rows(text, width);
+        let (row, col) = locate(text, &rows, app.input.cursor(), width);
+        // a cursor after a full last row sits on a row of its own
+        let total = rows.len().max(row + 1);
+        self.page = height;
+        self.area = area;
+        self.update_scroll(row, total, height);
+
+        let (line, column) = app.input.line_col();
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title(
+                Line::from(format!("[{}]", self.scroll_indicator(total, height))).right_aligned(),
+            )
+            .title_bottom(format!(" Ln {}, Col {} ", line + 1, column + 1));
+        let pending = self.vim.pending();
+        if app.vim && !pending.is_empty() {
+            block = block.title_bottom(Line::from(format!(" {} ", pending)).right_aligned());
+        }
 
-        let paragraph = Paragraph::new(app.input.as_str())
+        let lines = self.styled_rows(app, text, rows);
+        let paragraph = Paragraph::new(lines)
             .block(block)
-            .wrap(Wrap { trim: false })
             .scroll((self.scroll as u16, 0));
 
         f.render_widget(paragraph, area);
 
-        if self.focus {
-            let position = self.compute_cursor(area, &app.input);
+        if self.focus
+            && let Some(position) = self.compute_cursor(area, row, col)
+        {
             f.set_cursor_position(position);
         }
     }
@@ -121,12 +249,68 @@ impl Component for EditView {
             return;
         }
 
+        // scrolling leaves the cursor where it is, everything else brings it back into view
+        match action {
+            Action::ScrollUp => {
+                self.scroll = self.scroll.saturating_sub(SCROLL_STEP);
+                self.follow = false;
+                return;
+            }
+            Action::ScrollDown => {
+                self.scroll += SCROLL_STEP;
+                self.follow = false;
+                return;
+            }
+            _ => self.follow = true,
+        }
+        if let Action::Click { column, row } = action {
+            self.click(*column, *row, app);
+            return;
+        }
+
+        if app.vim {
+            let single_line = matches!(app.mode, Mode::AddTitle | Mode::EditTitle);
+            match action {
+                Action::Undo => {
+                    app.input.undo();
+                }
+                Action::Redo => {
+                    app.input.redo();
+                }
+                Action::PageUp => app.input.move_up(self.page),
+                Action::PageDown => app.input.move_down(self.page),
+                Action::Enter if single_line => self.handle_enter(app),
+                _ => {
+                    self.vim.handle(action, &mut app.input, single_line);
+                    return;
+                }
+            }
+            if self.vim.mode() != VimMode::Insert {
+                app.input.clamp_to_line();
+            }
+            return;
+        }
+
         match action {
-            Action::Char(c) => app.input.push(*c),
-            Action::Backspace => {
-                app.input.pop();
+            Action::Char(c) => app.input.insert_char(*c),
+            Action::Backspace => app.input.backspace(),
+            Action::Delete => app.input.delete(),
+            Action::Undo => {
+                app.input.undo();
             }
-            Action::Save => {}
+            Action::Redo => {
+                app.input.redo();
+            }
+            Action::Left => app.input.move_left(),
+            Action::Right => app.input.move_right(),
+            Action::WordLeft => app.input.move_word_left(),
+            Action::WordRight => app.input.move_word_right(),
+            Action::Home => app.input.move_line_start(),
+            Action::End => app.input.move_line_end(),
+            Action::Up => app.input.move_up(1),
+            Action::Down => app.input.move_down(1),
+            Action::PageUp => app.input.move_up(self.page),
+            Action::PageDown => app.input.move_down(self.page),
             Action::Enter => self.handle_enter(app),
             Action::Esc => app.mode = Mode::List,
             _ => {}
@@ -139,5 +323,176 @@ impl Component for EditView {
 
     fn set_focus(&mut self, focus: bool) {
         self.focus = focus;
+        self.follow = true;
+        if focus {
+            self.vim.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NoteClient;
+    use rusqlite::Connection;
+
+    fn editing(mode: Mode) -> (App, EditView) {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        let mut app = App::new(client);
+        app.mode = mode;
+        let mut edit = EditView::new();
+        edit.set_focus(true);
+        (app, edit)
+    }
+
+    fn replay(app: &mut App, edit: &mut EditView, actions: &[Action]) {
+        for action in actions {
+            edit.handle(action, app);
+        }
+    }
+
+    fn typing(text: &str) -> Vec<Action> {
+        text.chars()
+            .map(|c| {
+                if c == '\n' {
+                    Action::Enter
+                } else {
+                    Action::Char(c)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_undo_redo_typing_runs() {
+        let (mut app, mut edit) = editing(Mode::AddContent);
+        replay(&mut app, &mut edit, &typing("hello world\nbye"));
+        assert_eq!(app.input.as_str(), "hello world\nbye");
+
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!(app.input.as_str(), "hello world\n");
+        replay(&mut app, &mut edit, &[Action::Undo, Action::Undo]);
+        assert_eq!(app.input.as_str(), "hello ");
+        replay(&mut app, &mut edit, &[Action::Undo, Action::Undo]);
+        assert_eq!(app.input.as_str(), "");
+        // nothing left to undo
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!(app.input.as_str(), "");
+
+        replay(&mut app, &mut edit, &[Action::Redo, Action::Redo]);
+        assert_eq!(app.input.as_str(), "hello world");
+        assert_eq!(app.input.cursor(), "hello world".len());
+
+        // a new edit drops what could still be redone
+        replay(&mut app, &mut edit, &typing("!"));
+        replay(&mut app, &mut edit, &[Action::Redo]);
+        assert_eq!(app.input.as_str(), "hello world!");
+    }
+
+    #[test]
+    fn test_undo_deletions_and_cursor_moves() {
+        let (mut app, mut edit) = editing(Mode::EditContent);
+        replay(&mut app, &mut edit, &typing("abcdef"));
+        replay(
+            &mut app,
+            &mut edit,
+            &[
+                Action::Backspace,
+                Action::Backspace,
+                Action::Home,
+                Action::Delete,
+                Action::Delete,
+            ],
+        );
+        assert_eq!(app.input.as_str(), "cd");
+
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!((app.input.as_str(), app.input.cursor()), ("abcd", 0));
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!((app.input.as_str(), app.input.cursor()), ("abcdef", 6));
+
+        // typing after moving the cursor starts a new step
+        replay(&mut app, &mut edit, &[Action::Left, Action::Left]);
+        replay(&mut app, &mut edit, &typing("XY"));
+        assert_eq!(app.input.as_str(), "abcdXYef");
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!((app.input.as_str(), app.input.cursor()), ("abcdef", 4));
+    }
+
+    #[test]
+    fn test_title_and_content_have_separate_histories() {
+        let (mut app, mut edit) = editing(Mode::AddTitle);
+        replay(&mut app, &mut edit, &typing("Tilte"));
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!(app.input.as_str(), "");
+        replay(&mut app, &mut edit, &[Action::Redo]);
+        assert_eq!(app.input.as_str(), "Tilte");
+
+        replay(&mut app, &mut edit, &[Action::Enter]);
+        assert!(matches!(app.mode, Mode::AddContent));
+        assert_eq!(app.buffer, "Tilte");
+        // undo in the content phase cannot bring the title back
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!(app.input.as_str(), "");
+        replay(&mut app, &mut edit, &typing("body"));
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!(app.input.as_str(), "");
+    }
+
+    #[test]
+    fn test_vim_mode_keeps_esc_until_normal() {
+        let (mut app, mut edit) = editing(Mode::AddTitle);
+        app.vim = true;
+        replay(&mut app, &mut edit, &typing("iTitle"));
+        assert!(edit.captures_esc(&app));
+        replay(&mut app, &mut edit, &[Action::Esc]);
+        assert!(!edit.captures_esc(&app));
+        // Enter moves on from the title in any mode
+        replay(&mut app, &mut edit, &[Action::Enter]);
+        assert!(matches!(app.mode, Mode::AddContent));
+        assert_eq!(app.buffer, "Title");
+
+        replay(&mut app, &mut edit, &typing("ione\ntwo"));
+        replay(&mut app, &mut edit, &[Action::Esc]);
+        replay(&mut app, &mut edit, &typing("kdd"));
+        assert_eq!(app.input.as_str(), "two");
+        replay(&mut app, &mut edit, &[Action::Undo]);
+        assert_eq!(app.input.as_str(), "one\ntwo");
+    }
+
+    #[test]
+    fn test_click_places_cursor() {
+        let (mut app, mut edit) = editing(Mode::EditContent);
+        app.input.set_text("first\nsecond line");
+        edit.area = Rect::new(10, 5, 12, 6);
+        // "second line" wraps after "second lin" in the 10 columns inside the border
+        edit.handle(&Action::Click { column: 14, row: 7 }, &mut app);
+        assert_eq!(app.input.cursor(), "first\nsec".len());
+        edit.handle(&Action::Click { column: 20, row: 8 }, &mut app);
+        assert_eq!(app.input.cursor(), app.input.as_str().len());
+        // the border is not text
+        edit.handle(&Action::Click { column: 10, row: 6 }, &mut app);
+        assert_eq!(app.input.cursor(), app.input.as_str().len());
+    }
+
+    #[test]
+    fn test_scroll_follows_cursor_until_scrolled_away() {
+        let mut edit = EditView::new();
+        edit.update_scroll(25, 30, 10);
+        assert_eq!(edit.scroll, 16);
+        assert_eq!(edit.scroll_indicator(30, 10), "80%");
+        edit.update_scroll(3, 30, 10);
+        assert_eq!(edit.scroll, 3);
+
+        edit.follow = false;
+        edit.scroll = 18;
+        edit.update_scroll(3, 30, 10);
+        assert_eq!(edit.scroll, 18);
+        // never past the last full page
+        edit.scroll = 40;
+        edit.update_scroll(3, 30, 10);
+        assert_eq!(edit.scroll, 20);
+        assert_eq!(edit.scroll_indicator(30, 10), "Bot");
+        assert_eq!(edit.scroll_indicator(5, 10), "All");
     }
 }
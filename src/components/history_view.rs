@@ -0,0 +1,138 @@
+// src/components/history_view.rs
+
+use crate::{
+    app::App,
+    components::component::Component,
+    input::Action,
+    models::{note::Note, revision::Revision},
+};
+use chrono::Local;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+use similar::{ChangeTag, TextDiff};
+
+pub struct HistoryView {
+    state: ListState,
+    focus: bool,
+    note: Option<Note>,
+    revisions: Vec<Revision>,
+    selected: usize,
+}
+
+impl HistoryView {
+    pub fn new() -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Self {
+            state,
+            focus: false,
+            note: None,
+            revisions: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    /// Shows the revisions of `note`, newest first
+    pub fn open(&mut self, note: Note, revisions: Vec<Revision>) {
+        self.note = Some(note);
+        self.revisions = revisions;
+        self.selected = 0;
+    }
+
+    pub fn selected_revision(&self) -> Option<&Revision> {
+        self.revisions.get(self.selected)
+    }
+}
+
+// What restoring `revision` would change in `current`
+fn diff_lines(current: &Note, revision: &Revision) -> Vec<Line<'static>> {
+    let removed = Style::default().fg(Color::Red);
+    let added = Style::default().fg(Color::Green);
+    let mut lines = Vec::new();
+
+    if current.title != revision.title {
+        lines.push(Line::styled(format!("- title: {}", current.title), removed));
+        lines.push(Line::styled(format!("+ title: {}", revision.title), added));
+        lines.push(Line::raw(""));
+    }
+    let diff = TextDiff::from_lines(&current.content, &revision.content);
+    for change in diff.iter_all_changes() {
+        let (sign, style) = match change.tag() {
+            ChangeTag::Delete => ("-", removed),
+            ChangeTag::Insert => ("+", added),
+            ChangeTag::Equal => (" ", Style::default()),
+        };
+        let text = change.value().trim_end_matches('\n');
+        lines.push(Line::styled(format!("{} {}", sign, text), style));
+    }
+    if current.title == revision.title && current.content == revision.content {
+        lines.insert(0, Line::raw("(same as the current note)"));
+    }
+    lines
+}
+
+impl Component for HistoryView {
+    fn render(&mut self, f: &mut Frame, area: Rect, _app: &App) {
+        let Some(note) = &self.note else {
+            return;
+        };
+        if self.revisions.is_empty() {
+            let header = format!("{} — History (Esc=back)", note.title);
+            let p = Paragraph::new("No earlier versions of this note.")
+                .block(Block::default().borders(Borders::ALL).title(header));
+            f.render_widget(p, area);
+            return;
+        }
+
+        let [list_area, diff_area] =
+            Layout::horizontal([Constraint::Length(28), Constraint::Min(1)]).areas(area);
+
+        let items: Vec<ListItem> = self
+            .revisions
+            .iter()
+            .map(|r| {
+                ListItem::new(format!(
+                    "#{:<3} {}",
+                    r.revision,
+                    r.updated_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                ))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("History"))
+            .highlight_symbol("➤ ");
+        self.state.select(Some(self.selected));
+        f.render_stateful_widget(list, list_area, &mut self.state);
+
+        if let Some(revision) = self.selected_revision() {
+            let header = format!("{} — changes if restored (↵=restore, Esc=back)", note.title);
+            let p = Paragraph::new(diff_lines(note, revision))
+                .block(Block::default().borders(Borders::ALL).title(header))
+                .wrap(Wrap { trim: false });
+            f.render_widget(p, diff_area);
+        }
+    }
+
+    fn handle(&mut self, action: &Action, _app: &mut App) {
+        if !self.focus {
+            return;
+        }
+        match action {
+            Action::Up if self.selected > 0 => self.selected -= 1,
+            Action::Down if self.selected + 1 < self.revisions.len() => self.selected += 1,
+            _ => {}
+        }
+    }
+
+    fn focused(&self) -> bool {
+        self.focus
+    }
+    fn set_focus(&mut self, focus: bool) {
+        self.focus = focus;
+    }
+}
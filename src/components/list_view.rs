@@ -1,43 +1,217 @@
 // src/components/list_view.rs
 
-use crate::{app::App, components::component::Component, input::Action};
+use crate::{
+    app::App, components::component::Component, db::SortOrder, input::Action, keymap::Context,
+    models::note::Note,
+};
 use ratatui::{
-    layout::Rect,
-    text::Span,
-    widgets::{Block, Borders, List, ListItem, ListState},
     Frame,
+    layout::{Margin, Position, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
 };
 
+/// Notes moved per wheel notch
+const SCROLL_STEP: usize = 3;
+
 pub struct ListView {
     state: ListState,
     focus: bool,
+    // where the last render drew and how many rows each note took, to map clicks
+    area: Rect,
+    item_height: usize,
+    // the first note on screen and how many fit; only those are turned into rows
+    offset: usize,
+    rows: usize,
+    // the selected note was clicked again
+    open: bool,
 }
 
 impl ListView {
     pub fn new() -> Self {
         let mut state = ListState::default();
         state.select(Some(0));
-        Self { state, focus: false }
+        Self {
+            state,
+            focus: false,
+            area: Rect::default(),
+            item_height: 1,
+            offset: 0,
+            rows: 1,
+            open: false,
+        }
+    }
+
+    /// True once after the selected note was clicked, asking to open it
+    pub fn take_open(&mut self) -> bool {
+        std::mem::take(&mut self.open)
+    }
+
+    // Selects the clicked note, or asks to open it when it already was selected
+    fn click(&mut self, column: u16, row: u16, count: usize, app: &mut App) {
+        let inner = self.area.inner(Margin::new(1, 1));
+        if !inner.contains(Position::new(column, row)) {
+            return;
+        }
+        let index = self.offset + (row - inner.y) as usize / self.item_height;
+        if index >= count {
+            return;
+        }
+        if index == app.selected {
+            self.open = true;
+        } else {
+            app.selected = index;
+        }
+    }
+
+    // Scrolls just enough to keep the selected note among the `rows` on screen
+    fn update_offset(&mut self, selected: usize, len: usize, rows: usize) {
+        if selected < self.offset {
+            self.offset = selected;
+        } else if selected >= self.offset + rows {
+            self.offset = selected + 1 - rows;
+        }
+        self.offset = self.offset.min(len.saturating_sub(rows));
     }
 }
 
+// The note title between its pin and favorite markers, followed by how many of its
+// tasks are done and by its tags
+fn title_line(note: &Note, (done, total): (usize, usize)) -> Line<'static> {
+    let mut spans = Vec::new();
+    if note.pinned {
+        spans.push(Span::styled("⚑ ", Style::default().fg(Color::Red)));
+    }
+    spans.push(Span::raw(note.title.clone()));
+    if note.favorite {
+        spans.push(Span::styled(" ★", Style::default().fg(Color::Yellow)));
+    }
+    if total > 0 {
+        let color = if done == total {
+            Color::Green
+        } else {
+            Color::DarkGray
+        };
+        spans.push(Span::styled(
+            format!(" [{}/{}]", done, total),
+            Style::default().fg(color),
+        ));
+    }
+    for tag in &note.tags {
+        spans.push(Span::styled(
+            format!(" #{}", tag),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    Line::from(spans)
+}
+
 impl Component for ListView {
     fn render(&mut self, f: &mut Frame, area: Rect, app: &App) {
-        let notes = app.note_client.get_all_notes().unwrap_or_default();
-        let items: Vec<ListItem> = notes
-            .iter()
-            .map(|n| ListItem::new(Span::raw(n.title.clone())))
-            .collect();
+        // search hits show a snippet under the title
+        self.item_height = if app.active_query().is_some() { 2 } else { 1 };
+        self.area = area;
+        let rows = (area.height.saturating_sub(2) as usize / self.item_height).max(1);
+        self.rows = rows;
+        let count = app.note_count();
+        self.update_offset(app.selected, count, rows);
+        let window = self.offset..(self.offset + rows).min(count);
 
+        let items: Vec<ListItem> = if app.active_query().is_some() {
+            app.search_hits()[window]
+                .iter()
+                .map(|hit| {
+                    let snippet: Vec<Span> = hit
+                        .segments()
+                        .into_iter()
+                        .map(|(text, matched)| {
+                            let text = text.replace('\n', " ");
+                            if matched {
+                                Span::styled(
+                                    text,
+                                    Style::default()
+                                        .fg(Color::Yellow)
+                                        .add_modifier(Modifier::BOLD),
+                                )
+                            } else {
+                                Span::styled(text, Style::default().fg(Color::DarkGray))
+                            }
+                        })
+                        .collect();
+                    ListItem::new(vec![
+                        title_line(&hit.note, app.task_counts(&hit.note)),
+                        Line::from(snippet),
+                    ])
+                })
+                .collect()
+        } else {
+            app.notes_in(window)
+                .iter()
+                .map(|n| ListItem::new(title_line(n, app.task_counts(n))))
+                .collect()
+        };
+
+        // search hits come by relevance whatever the sort order
+        let mut title = match app.active_query() {
+            Some(_) => String::from("Notes [best match]"),
+            None => format!("Notes [{}]", app.sort.label()),
+        };
+        if app.favorites {
+            title.push_str(" ★");
+        }
+        if let Some(id) = app.notebook {
+            let tree = app.notebook_tree();
+            if let Some(entry) = tree.iter().find(|e| e.notebook.id == id) {
+                title.push_str(&format!(" in {}", entry.path));
+            }
+        }
+        if let Some(tag) = &app.tag {
+            title.push_str(&format!(" #{}", tag));
+        }
+        if let Some(query) = app.active_query() {
+            title.push_str(&format!(" matching \"{}\"", query));
+        }
+        if app.last_trashed.is_some() {
+            title.push_str(&format!(
+                " — moved to trash, {}:undo",
+                app.keymap.key(Action::UndoDelete)
+            ));
+        }
+        if let Some(status) = &app.status {
+            title.push_str(&format!(" — {}", status));
+        }
+        let mut hint = app.keymap.hint(Context::List);
+        if app.sort == SortOrder::Manual {
+            hint.push_str(&format!(
+                ", {}/{}:move",
+                app.keymap.key(Action::MoveUp),
+                app.keymap.key(Action::MoveDown)
+            ));
+        }
+        if app.tag.is_some() || app.query.is_some() || app.favorites || app.notebook.is_some() {
+            title.push_str(&format!(" (Esc:clear filter, {})", hint));
+        } else {
+            title.push_str(&format!(" ({})", hint));
+        }
+
+        let page = format!(
+            " page {} of {} ",
+            app.selected / rows + 1,
+            count.div_ceil(rows).max(1)
+        );
         let list = List::new(items)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Notes (a:add, e:edit, p:preview, d: delete, q:quit)"),
+                    .title(title)
+                    .title_bottom(Line::from(page).right_aligned()),
             )
             .highlight_symbol("➤ ");
 
-        self.state.select(Some(app.selected));
+        self.state
+            .select(Some(app.selected.saturating_sub(self.offset)));
+        *self.state.offset_mut() = 0;
         f.render_stateful_widget(list, area, &mut self.state);
     }
 
@@ -45,14 +219,30 @@ impl Component for ListView {
         if !self.focus {
             return;
         }
-        let notes = app.note_client.get_all_notes().unwrap_or_default();
+        let count = app.note_count();
+        let last = count.saturating_sub(1);
         match action {
             Action::Up if app.selected > 0 => {
                 app.selected -= 1;
             }
-            Action::Down if app.selected + 1 < notes.len() => {
+            Action::Down if app.selected + 1 < count => {
                 app.selected += 1;
             }
+            Action::ScrollUp => {
+                app.selected = app.selected.saturating_sub(SCROLL_STEP);
+            }
+            Action::ScrollDown => {
+                app.selected = (app.selected + SCROLL_STEP).min(last);
+            }
+            Action::PageUp => {
+                app.selected = app.selected.saturating_sub(self.rows);
+            }
+            Action::PageDown => {
+                app.selected = (app.selected + self.rows).min(last);
+            }
+            Action::Home => app.selected = 0,
+            Action::End => app.selected = last,
+            Action::Click { column, row } => self.click(*column, *row, count, app),
             _ => {} // a/e/p/q will be handled in controller
         }
     }
@@ -64,3 +254,74 @@ impl Component for ListView {
         self.focus = focus;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NoteClient;
+    use ratatui::{Terminal, backend::TestBackend};
+    use rusqlite::Connection;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_offset_keeps_selection_on_screen() {
+        let mut list = ListView::new();
+        list.update_offset(12, 100, 10);
+        assert_eq!(list.offset, 3);
+        list.update_offset(8, 100, 10);
+        assert_eq!(list.offset, 3);
+        list.update_offset(1, 100, 10);
+        assert_eq!(list.offset, 1);
+        // a taller screen shows the end of the list rather than blank rows
+        list.offset = 95;
+        list.update_offset(99, 100, 20);
+        assert_eq!(list.offset, 80);
+    }
+
+    #[test]
+    fn test_title_line_counts_tasks() {
+        let app = App::new(NoteClient::new(Connection::open_in_memory().unwrap()).unwrap());
+        let text = |note: &Note| -> String {
+            title_line(note, app.task_counts(note))
+                .spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect()
+        };
+        let note = Note::new("Groceries", "- [x] milk\n- [ ] eggs #shop");
+        assert_eq!(text(&note), "Groceries [1/2] #shop");
+        assert_eq!(text(&Note::new("Plain", "no list")), "Plain");
+    }
+
+    // Average time to draw the list with `count` notes, once the cache is warm
+    fn render_cost(count: usize) -> Duration {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        for i in 0..count {
+            client
+                .add_note(&Note::new(&format!("Note {}", i), "body"))
+                .unwrap();
+        }
+        let mut app = App::new(client);
+        app.selected = count / 2;
+        let mut list = ListView::new();
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| list.render(f, f.area(), &app)).unwrap();
+
+        let frames = 200;
+        let start = Instant::now();
+        for _ in 0..frames {
+            terminal.draw(|f| list.render(f, f.area(), &app)).unwrap();
+        }
+        start.elapsed() / frames
+    }
+
+    // cargo test --release -- --ignored --nocapture render_cost
+    #[test]
+    #[ignore]
+    fn bench_render_cost_with_50k_notes() {
+        let small = render_cost(100);
+        let large = render_cost(50_000);
+        println!("per frame: {small:?} with 100 notes, {large:?} with 50k notes");
+        assert!(large < small * 3);
+    }
+}
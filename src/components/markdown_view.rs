@@ -1,15 +1,59 @@
 // src/components/markdown_view.rs
 
-use crate::{app::App, components::component::Component, input::Action};
+use crate::{
+    app::App,
+    components::component::Component,
+    input::Action,
+    markdown::{self, Highlights},
+    models::note::Note,
+};
 use ratatui::{
-    layout::Rect,
-    widgets::{Block, Borders, Paragraph},
     Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, Paragraph},
 };
+use uuid::Uuid;
+
+/// Most backlinks listed under the preview at once
+const BACKLINK_ROWS: usize = 5;
+
+// What a rendering depends on; the same key gives the same lines
+#[derive(PartialEq)]
+struct RenderKey {
+    note: Option<Uuid>,
+    generation: u64,
+    width: usize,
+    theme: String,
+    link: Option<usize>,
+    task: Option<usize>,
+}
 
 pub struct MarkdownView {
     focus: bool,
-    scroll: u16,
+    scroll: usize,
+    // rendered height and last scroll position of the previous frame
+    page: usize,
+    max_scroll: usize,
+    // show the note selected in the list rather than the one opened for preview
+    selection: bool,
+    // the note shown in the previous frame, to start each new one at the top
+    shown: Option<Uuid>,
+    // the note opened for preview, with the notes linking to it and the titles it links to
+    note: Option<Note>,
+    backlinks: Vec<Note>,
+    links: Vec<String>,
+    // the highlighted link, counting the note's own links first and then its backlinks
+    link: Option<usize>,
+    // the highlighted checklist item and how many the note has
+    task: Option<usize>,
+    task_count: usize,
+    // notes left by following links, to go back to
+    trail: Vec<Note>,
+    message: Option<String>,
+    // the lines drawn last, reused until the note, the database or the size changes
+    rendered: Option<(RenderKey, Vec<Line<'static>>)>,
 }
 
 impl MarkdownView {
@@ -17,35 +61,302 @@ impl MarkdownView {
         Self {
             focus: false,
             scroll: 0,
+            page: 1,
+            max_scroll: 0,
+            selection: false,
+            shown: None,
+            note: None,
+            backlinks: Vec::new(),
+            links: Vec::new(),
+            link: None,
+            task: None,
+            task_count: 0,
+            trail: Vec::new(),
+            message: None,
+            rendered: None,
+        }
+    }
+
+    /// Opens a note for preview, forgetting the notes followed before
+    pub fn open(&mut self, note: Note, app: &App) {
+        self.trail.clear();
+        self.show(note, app);
+    }
+
+    /// Opens a note for preview with one of its tasks highlighted
+    pub fn open_at_task(&mut self, note: Note, task: usize, app: &App) {
+        self.open(note, app);
+        if task < self.task_count {
+            self.task = Some(task);
+        }
+    }
+
+    fn show(&mut self, note: Note, app: &App) {
+        self.backlinks = app
+            .note_client
+            .backlinks(&note.title, note.id)
+            .unwrap_or_default();
+        self.links = markdown::wiki_links(&note.content);
+        self.link = (!self.links.is_empty() || !self.backlinks.is_empty()).then_some(0);
+        self.task_count = markdown::tasks(&note.content).len();
+        self.task = (self.task_count > 0).then_some(0);
+        self.note = Some(note);
+        self.scroll = 0;
+    }
+
+    // Moves the highlight to the next link, or the previous one going `back`
+    fn cycle_link(&mut self, back: bool) {
+        let count = self.links.len() + self.backlinks.len();
+        if count == 0 {
+            return;
+        }
+        self.link = Some(match self.link {
+            Some(i) if back => (i + count - 1) % count,
+            Some(i) => (i + 1) % count,
+            None => 0,
+        });
+    }
+
+    // Moves the highlight to the next task, or the previous one going `back`
+    fn cycle_task(&mut self, back: bool) {
+        let count = self.task_count;
+        if count == 0 {
+            return;
+        }
+        self.task = Some(match self.task {
+            Some(i) if back => (i + count - 1) % count,
+            Some(i) => (i + 1) % count,
+            None => 0,
+        });
+    }
+
+    // Checks or unchecks the highlighted task and saves the note
+    fn toggle_task(&mut self, app: &App) {
+        let (Some(index), Some(note)) = (self.task, &mut self.note) else {
+            return;
+        };
+        let Some(content) = markdown::toggle_task(&note.content, index) else {
+            return;
+        };
+        let mut updated = note.clone();
+        updated.content = content;
+        match app.note_client.update_note(&mut updated) {
+            Ok(_) => *note = updated,
+            Err(e) => self.message = Some(format!("task not saved: {}", e)),
+        }
+    }
+
+    // Opens the note behind the highlighted link, keeping the current one to go back to
+    fn follow(&mut self, app: &App) {
+        let Some(index) = self.link else {
+            return;
+        };
+        let target = match self.links.get(index) {
+            Some(title) => match app.note_client.note_by_title(title) {
+                Ok(Some(note)) => note,
+                _ => {
+                    self.message = Some(format!("no note titled \"{}\"", title));
+                    return;
+                }
+            },
+            None => match self.backlinks.get(index - self.links.len()) {
+                Some(note) => note.clone(),
+                None => return,
+            },
+        };
+        if let Some(current) = self.note.take() {
+            self.trail.push(current);
+        }
+        self.show(target, app);
+    }
+
+    /// A preview pane that follows the selection of the list
+    pub fn for_selection() -> Self {
+        Self {
+            selection: true,
+            ..Self::new()
+        }
+    }
+
+    // Title and Markdown of the selected note
+    fn selected_note(&mut self, app: &App) -> (String, String) {
+        let note = app.selected_note();
+        let id = note.as_ref().map(|n| n.id);
+        if id != self.shown {
+            self.shown = id;
+            self.scroll = 0;
         }
+        let key = app.keymap.key(Action::SwitchFocus);
+        let hint = if self.focus {
+            format!("{} to list", key)
+        } else {
+            format!("{} to focus", key)
+        };
+        match note {
+            Some(n) => (format!("{} ({})", n.title, hint), n.content),
+            None => (format!("Preview ({})", hint), String::new()),
+        }
+    }
+
+    // The notes linking to the previewed one, the highlighted one reversed
+    fn render_backlinks(&self, f: &mut Frame, area: Rect) {
+        let selected = self.link.and_then(|i| i.checked_sub(self.links.len()));
+        let rows = area.height.saturating_sub(2) as usize;
+        // keep the highlighted backlink in view
+        let first = selected.map_or(0, |i| (i + 1).saturating_sub(rows));
+        let items: Vec<ListItem> = self
+            .backlinks
+            .iter()
+            .enumerate()
+            .skip(first)
+            .map(|(i, note)| {
+                let style = if selected == Some(i) {
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().fg(Color::Magenta)
+                };
+                ListItem::new(Line::styled(note.title.clone(), style))
+            })
+            .collect();
+        let title = format!("Linked from ({})", self.backlinks.len());
+        f.render_widget(
+            List::new(items).block(Block::default().borders(Borders::ALL).title(title)),
+            area,
+        );
     }
 }
 
 impl Component for MarkdownView {
     fn render(&mut self, f: &mut Frame, area: Rect, app: &App) {
-        // use app.buffer as the title, and app.input as the body
-        let header = if app.buffer.is_empty() {
-            "Preview (Esc to list)".to_string()
+        let (header, text) = if self.selection {
+            self.selected_note(app)
         } else {
-            format!("{} — Preview (Esc to list)", app.buffer)
+            let (title, content) = self
+                .note
+                .as_ref()
+                .map_or((String::new(), String::new()), |n| {
+                    (n.title.clone(), n.content.clone())
+                });
+            let mut hints = vec!["Esc to list".to_string()];
+            if self.link.is_some() {
+                hints.push(format!(
+                    "{}:next link, ↵:follow",
+                    app.keymap.key(Action::NextLink)
+                ));
+            }
+            if self.task.is_some() {
+                hints.push(format!(
+                    "{}:next task, {}:toggle",
+                    app.keymap.key(Action::NextTask),
+                    app.keymap.key(Action::ToggleTask)
+                ));
+            }
+            if !self.trail.is_empty() {
+                hints.push("⌫:back".to_string());
+            }
+            (
+                format!("{} — Preview ({})", title, hints.join(", ")),
+                content,
+            )
         };
 
-        let p = Paragraph::new(app.input.as_str())
-            .block(Block::default().borders(Borders::ALL).title(header))
-            .scroll((self.scroll, 0));
+        // the notes linking here are listed under the text
+        let area = if self.selection || self.backlinks.is_empty() {
+            area
+        } else {
+            let rows = self.backlinks.len().min(BACKLINK_ROWS) as u16 + 2;
+            let [text_area, list_area] =
+                Layout::vertical([Constraint::Min(1), Constraint::Length(rows)]).areas(area);
+            self.render_backlinks(f, list_area);
+            text_area
+        };
+
+        let width = area.width.saturating_sub(2) as usize;
+        let height = area.height.saturating_sub(2) as usize;
+        let key = RenderKey {
+            note: if self.selection {
+                self.shown
+            } else {
+                self.note.as_ref().map(|n| n.id)
+            },
+            generation: app.note_client.generation(),
+            width,
+            theme: app.theme.clone(),
+            link: self.link.filter(|_| !self.selection),
+            task: self.task.filter(|_| !self.selection),
+        };
+        if self.rendered.as_ref().is_none_or(|(k, _)| *k != key) {
+            let titles = app.link_titles();
+            let highlights = Highlights {
+                titles: Some(&titles),
+                link: key.link,
+                task: key.task,
+            };
+            let lines = markdown::render(&text, width, &app.theme, &highlights);
+            self.rendered = Some((key, lines));
+        }
+        let lines = self
+            .rendered
+            .as_ref()
+            .map(|(_, lines)| lines.clone())
+            .unwrap_or_default();
+        self.page = height.max(1);
+        self.max_scroll = lines.len().saturating_sub(height);
+        self.scroll = self.scroll.min(self.max_scroll);
+
+        let position = match (self.scroll * 100).checked_div(self.max_scroll) {
+            Some(percent) => format!("{}%", percent),
+            None => "All".to_string(),
+        };
+        // a focused pane stands out from the list next to it
+        let border = if self.selection && self.focus {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border)
+            .title(header)
+            .title(Line::from(format!("[{}]", position)).right_aligned());
+        if let Some(message) = &self.message {
+            block = block.title_bottom(Line::from(format!(" {} ", message)).right_aligned());
+        }
+        let p = Paragraph::new(lines)
+            .block(block)
+            .scroll((self.scroll as u16, 0));
 
         f.render_widget(p, area);
     }
 
-    fn handle(&mut self, action: &Action, _app: &mut App) {
+    fn handle(&mut self, action: &Action, app: &mut App) {
         if !self.focus {
             return;
         }
+        self.message = None;
         match action {
-            Action::Up => self.scroll = self.scroll.saturating_sub(1),
-            Action::Down => self.scroll = self.scroll.saturating_add(1),
+            Action::NextLink => self.cycle_link(false),
+            Action::PrevLink => self.cycle_link(true),
+            Action::NextTask => self.cycle_task(false),
+            Action::PrevTask => self.cycle_task(true),
+            Action::ToggleTask if !self.selection => self.toggle_task(app),
+            Action::Enter if !self.selection => self.follow(app),
+            Action::Backspace => {
+                if let Some(note) = self.trail.pop() {
+                    self.show(note, app);
+                }
+            }
+            Action::Up | Action::ScrollUp => self.scroll = self.scroll.saturating_sub(1),
+            Action::Down | Action::ScrollDown => self.scroll += 1,
+            Action::PageUp => self.scroll = self.scroll.saturating_sub(self.page),
+            Action::PageDown => self.scroll += self.page,
+            Action::Home => self.scroll = 0,
+            Action::End => self.scroll = self.max_scroll,
             _ => {}
         }
+        self.scroll = self.scroll.min(self.max_scroll);
     }
 
     fn focused(&self) -> bool {
@@ -53,5 +364,39 @@ impl Component for MarkdownView {
     }
     fn set_focus(&mut self, focus: bool) {
         self.focus = focus;
+        if focus && !self.selection {
+            self.scroll = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NoteClient;
+    use ratatui::{Terminal, backend::TestBackend};
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_lines_are_rendered_again_only_after_a_change() {
+        let client = NoteClient::new(Connection::open_in_memory().unwrap()).unwrap();
+        let mut note = Note::new("Code", "```rust\nfn main() {}\n```");
+        client.add_note(&note).unwrap();
+        let app = App::new(client);
+        let mut view = MarkdownView::for_selection();
+        let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+        let mut draw = |view: &mut MarkdownView| {
+            terminal.draw(|f| view.render(f, f.area(), &app)).unwrap();
+            view.rendered.as_ref().unwrap().1.as_ptr()
+        };
+
+        let first = draw(&mut view);
+        assert_eq!(draw(&mut view), first);
+
+        note.content = "changed".to_string();
+        app.note_client.update_note(&mut note).unwrap();
+        draw(&mut view);
+        let (_, lines) = view.rendered.as_ref().unwrap();
+        assert_eq!(lines[0].spans[0].content, "changed");
     }
 }
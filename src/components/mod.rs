@@ -3,5 +3,11 @@
 pub mod component;
 pub mod confirm_dialog;
 pub mod edit_view;
+pub mod history_view;
 pub mod list_view;
 pub mod markdown_view;
+pub mod notebook_tree;
+pub mod search_bar;
+pub mod tag_sidebar;
+pub mod task_view;
+pub mod trash_view;
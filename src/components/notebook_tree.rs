@@ -0,0 +1,169 @@
+// src/components/notebook_tree.rs
+
+use crate::{app::App, components::component::Component, input::Action};
+use ratatui::{
+    Frame,
+    layout::{Position, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+use uuid::Uuid;
+
+pub struct NotebookTree {
+    state: ListState,
+    focus: bool,
+    // 0 is "all notes", or "no notebook" while moving; notebooks follow in tree order
+    selected: usize,
+    // picking a notebook for the selected note rather than a filter
+    moving: bool,
+    // the name typed for a new notebook below the highlighted one
+    naming: Option<String>,
+}
+
+impl NotebookTree {
+    pub fn new() -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Self {
+            state,
+            focus: false,
+            selected: 0,
+            moving: false,
+            naming: None,
+        }
+    }
+
+    /// Opens the tree to filter the list, highlighting the notebook filtering it now
+    pub fn start_filtering(&mut self, app: &App) {
+        self.moving = false;
+        self.highlight(app, app.notebook);
+    }
+
+    /// Opens the tree to move the selected note, highlighting the notebook it is in
+    pub fn start_moving(&mut self, app: &App) {
+        self.moving = true;
+        let current = app.selected_note().and_then(|n| n.notebook_id);
+        self.highlight(app, current);
+    }
+
+    /// Moves the highlight onto a notebook, or onto the first row for `None`
+    pub fn highlight(&mut self, app: &App, notebook: Option<Uuid>) {
+        let tree = app.notebook_tree();
+        self.selected = notebook
+            .and_then(|id| tree.iter().position(|e| e.notebook.id == id))
+            .map_or(0, |i| i + 1);
+    }
+
+    pub fn moving(&self) -> bool {
+        self.moving
+    }
+
+    /// The notebook under the highlight, `None` for the first row
+    pub fn selected_notebook(&self, app: &App) -> Option<Uuid> {
+        let tree = app.notebook_tree();
+        self.selected
+            .checked_sub(1)
+            .and_then(|i| tree.get(i))
+            .map(|e| e.notebook.id)
+    }
+
+    /// Starts typing the name of a new notebook
+    pub fn start_naming(&mut self) {
+        self.naming = Some(String::new());
+    }
+
+    pub fn naming(&self) -> bool {
+        self.naming.is_some()
+    }
+
+    /// Ends naming, returning the name typed
+    pub fn take_name(&mut self) -> Option<String> {
+        self.naming.take()
+    }
+}
+
+impl Component for NotebookTree {
+    fn render(&mut self, f: &mut Frame, area: Rect, app: &App) {
+        let tree = app.notebook_tree();
+        let first = if self.moving {
+            "No notebook"
+        } else {
+            "All notes"
+        };
+        let mut items = vec![ListItem::new(first)];
+        items.extend(tree.iter().map(|entry| {
+            ListItem::new(Line::from(vec![
+                Span::raw("  ".repeat(entry.depth)),
+                Span::styled(
+                    entry.notebook.name.clone(),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::styled(
+                    format!(" ({})", entry.count),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        }));
+
+        let new = app.keymap.key(Action::NewNotebook);
+        let title = match (self.focus, self.moving) {
+            (true, true) => format!("Move to (↵=move, {}=new, Esc=cancel)", new),
+            (true, false) => format!("Notebooks (↵=filter, {}=new, Esc=back)", new),
+            (false, _) => format!("Notebooks ({})", app.keymap.key(Action::Notebooks)),
+        };
+        let mut block = Block::default().borders(Borders::ALL).title(title);
+        if let Some(name) = &self.naming {
+            block = block.title_bottom(format!("new: {}", name));
+            let x = area.x + 1 + 5 + name.chars().count() as u16;
+            f.set_cursor_position(Position::new(
+                x.min(area.right().saturating_sub(2)),
+                area.bottom().saturating_sub(1),
+            ));
+        }
+        let mut list = List::new(items).block(block);
+        if self.focus {
+            list = list.highlight_symbol("➤ ");
+        }
+
+        self.state.select(Some(self.selected));
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn handle(&mut self, action: &Action, app: &mut App) {
+        if !self.focus {
+            return;
+        }
+        if let Some(name) = &mut self.naming {
+            match action {
+                // the path separator would make the name unreachable
+                Action::Char('/') => {}
+                Action::Char(c) => name.push(*c),
+                Action::Backspace => {
+                    name.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        let len = app.notebook_tree().len() + 1;
+        match action {
+            Action::Up if self.selected > 0 => self.selected -= 1,
+            Action::Down if self.selected + 1 < len => self.selected += 1,
+            Action::Home => self.selected = 0,
+            Action::End => self.selected = len - 1,
+            _ => {}
+        }
+    }
+
+    fn focused(&self) -> bool {
+        self.focus
+    }
+    fn set_focus(&mut self, focus: bool) {
+        self.focus = focus;
+        if !focus {
+            self.moving = false;
+            self.naming = None;
+        }
+    }
+}
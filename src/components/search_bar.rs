@@ -0,0 +1,59 @@
+// src/components/search_bar.rs
+
+use crate::{app::App, components::component::Component, input::Action};
+use ratatui::{
+    Frame,
+    layout::{Position, Rect},
+    widgets::{Block, Borders, Paragraph},
+};
+
+pub struct SearchBar {
+    focus: bool,
+}
+
+impl SearchBar {
+    pub fn new() -> Self {
+        Self { focus: false }
+    }
+}
+
+impl Component for SearchBar {
+    fn render(&mut self, f: &mut Frame, area: Rect, app: &App) {
+        let query = app.query.as_deref().unwrap_or_default();
+        let p = Paragraph::new(format!("/{}", query)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Search (↵=keep filter, Esc=clear)"),
+        );
+        f.render_widget(p, area);
+
+        if self.focus {
+            let col = query.chars().count() as u16 + 1;
+            let x = (area.x + 1 + col).min(area.right().saturating_sub(2));
+            f.set_cursor_position(Position::new(x, area.y + 1));
+        }
+    }
+
+    fn handle(&mut self, action: &Action, app: &mut App) {
+        if !self.focus {
+            return;
+        }
+        let query = app.query.get_or_insert_with(String::new);
+        match action {
+            Action::Char(c) => query.push(*c),
+            Action::Backspace => {
+                query.pop();
+            }
+            _ => return,
+        }
+        // the result list changed underneath the selection
+        app.selected = 0;
+    }
+
+    fn focused(&self) -> bool {
+        self.focus
+    }
+    fn set_focus(&mut self, focus: bool) {
+        self.focus = focus;
+    }
+}
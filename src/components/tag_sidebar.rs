@@ -0,0 +1,104 @@
+// src/components/tag_sidebar.rs
+
+use crate::{app::App, components::component::Component, input::Action};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+/// Width the sidebar takes next to the note list
+pub const SIDEBAR_WIDTH: u16 = 24;
+
+pub struct TagSidebar {
+    state: ListState,
+    focus: bool,
+    // 0 is "all notes", tags follow in name order
+    selected: usize,
+}
+
+impl TagSidebar {
+    pub fn new() -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Self {
+            state,
+            focus: false,
+            selected: 0,
+        }
+    }
+
+    /// Moves the highlight onto the tag currently filtering the list
+    pub fn sync(&mut self, app: &App) {
+        let tags = app.tags_with_counts();
+        self.selected = app
+            .tag
+            .as_ref()
+            .and_then(|tag| tags.iter().position(|(name, _)| name == tag))
+            .map_or(0, |i| i + 1);
+    }
+
+    /// The tag under the highlight, `None` for "all notes"
+    pub fn selected_tag(&self, app: &App) -> Option<String> {
+        let tags = app.tags_with_counts();
+        self.selected
+            .checked_sub(1)
+            .and_then(|i| tags.get(i))
+            .map(|(name, _)| name.clone())
+    }
+}
+
+impl Component for TagSidebar {
+    fn render(&mut self, f: &mut Frame, area: Rect, app: &App) {
+        let tags = app.tags_with_counts();
+        let mut items = vec![ListItem::new("All notes")];
+        items.extend(tags.iter().map(|(name, count)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("#{}", name), Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!(" ({})", count),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        }));
+
+        let title = if self.focus {
+            "Tags (↵=filter, Esc=back)"
+        } else {
+            "Tags (t)"
+        };
+        let mut list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+        if self.focus {
+            list = list.highlight_symbol("➤ ");
+        }
+
+        self.state.select(Some(self.selected));
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn handle(&mut self, action: &Action, app: &mut App) {
+        if !self.focus {
+            return;
+        }
+        let len = app
+            .note_client
+            .all_tags_with_counts()
+            .unwrap_or_default()
+            .len()
+            + 1;
+        match action {
+            Action::Up if self.selected > 0 => self.selected -= 1,
+            Action::Down if self.selected + 1 < len => self.selected += 1,
+            _ => {}
+        }
+    }
+
+    fn focused(&self) -> bool {
+        self.focus
+    }
+    fn set_focus(&mut self, focus: bool) {
+        self.focus = focus;
+    }
+}
@@ -0,0 +1,134 @@
+// src/components/task_view.rs
+
+use crate::{
+    app::{App, OpenTask},
+    components::component::Component,
+    input::Action,
+    markdown,
+};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+pub struct TaskView {
+    state: ListState,
+    focus: bool,
+    selected: usize,
+    // a task was opened in the preview, which goes back here when closed
+    previewing: bool,
+    message: Option<String>,
+}
+
+impl TaskView {
+    pub fn new() -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Self {
+            state,
+            focus: false,
+            selected: 0,
+            previewing: false,
+            message: None,
+        }
+    }
+
+    pub fn selected_task(&self, app: &App) -> Option<OpenTask> {
+        app.open_tasks().get(self.selected).cloned()
+    }
+
+    /// Remembers that the preview was opened from here
+    pub fn start_preview(&mut self) {
+        self.previewing = true;
+    }
+
+    /// True once after a preview opened from here was closed
+    pub fn take_previewing(&mut self) -> bool {
+        std::mem::take(&mut self.previewing)
+    }
+
+    /// Keeps the highlight inside the list after tasks were checked elsewhere
+    pub fn clamp(&mut self, app: &App) {
+        let len = app.open_tasks().len();
+        self.selected = self.selected.min(len.saturating_sub(1));
+    }
+
+    // Checks off the highlighted task, which then leaves the list
+    fn check(&mut self, app: &App) {
+        let Some(task) = self.selected_task(app) else {
+            return;
+        };
+        let Some(content) = markdown::toggle_task(&task.note.content, task.index) else {
+            return;
+        };
+        let mut note = task.note;
+        note.content = content;
+        if let Err(e) = app.note_client.update_note(&mut note) {
+            self.message = Some(format!("task not saved: {}", e));
+        }
+        self.clamp(app);
+    }
+}
+
+impl Component for TaskView {
+    fn render(&mut self, f: &mut Frame, area: Rect, app: &App) {
+        let tasks = app.open_tasks();
+        let items: Vec<ListItem> = tasks
+            .iter()
+            .map(|task| {
+                ListItem::new(Line::from(vec![
+                    Span::styled("[ ] ", Style::default().fg(Color::Cyan)),
+                    Span::raw(task.text.clone()),
+                    Span::styled(
+                        format!("  {}", task.note.title),
+                        Style::default().fg(Color::Magenta),
+                    ),
+                ]))
+            })
+            .collect();
+
+        let title = if tasks.is_empty() {
+            "No open tasks (Esc=back)".to_string()
+        } else {
+            format!(
+                "Open tasks ({}, {}:check, ↵=open note, Esc=back)",
+                tasks.len(),
+                app.keymap.key(Action::ToggleTask)
+            )
+        };
+        let mut block = Block::default().borders(Borders::ALL).title(title);
+        if let Some(message) = &self.message {
+            block = block.title_bottom(Line::from(format!(" {} ", message)).right_aligned());
+        }
+        let list = List::new(items).block(block).highlight_symbol("➤ ");
+
+        self.state.select(Some(self.selected));
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn handle(&mut self, action: &Action, app: &mut App) {
+        if !self.focus {
+            return;
+        }
+        self.message = None;
+        let len = app.open_tasks().len();
+        match action {
+            Action::ToggleTask => self.check(app),
+            Action::Up if self.selected > 0 => self.selected -= 1,
+            Action::Down if self.selected + 1 < len => self.selected += 1,
+            Action::Home => self.selected = 0,
+            Action::End => self.selected = len.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    fn focused(&self) -> bool {
+        self.focus
+    }
+    fn set_focus(&mut self, focus: bool) {
+        self.focus = focus;
+    }
+}
@@ -0,0 +1,96 @@
+// src/components/trash_view.rs
+
+use crate::{
+    app::App, components::component::Component, input::Action, keymap::Context, models::note::Note,
+};
+use chrono::Local;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+pub struct TrashView {
+    state: ListState,
+    focus: bool,
+    selected: usize,
+}
+
+impl TrashView {
+    pub fn new() -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Self {
+            state,
+            focus: false,
+            selected: 0,
+        }
+    }
+
+    pub fn selected_note(&self, app: &App) -> Option<Note> {
+        let notes = app.note_client.trashed_notes().unwrap_or_default();
+        notes.into_iter().nth(self.selected)
+    }
+
+    /// Keeps the highlight inside the list after a note left the trash
+    pub fn clamp(&mut self, app: &App) {
+        let len = app.note_client.trashed_notes().unwrap_or_default().len();
+        self.selected = self.selected.min(len.saturating_sub(1));
+    }
+}
+
+impl Component for TrashView {
+    fn render(&mut self, f: &mut Frame, area: Rect, app: &App) {
+        let notes = app.note_client.trashed_notes().unwrap_or_default();
+        let items: Vec<ListItem> = notes
+            .iter()
+            .map(|n| {
+                let deleted = n
+                    .deleted_at
+                    .map(|d| {
+                        d.with_timezone(&Local)
+                            .format(" deleted %Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::raw(n.title.clone()),
+                    Span::styled(deleted, Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+
+        let title = if notes.is_empty() {
+            "Trash is empty (Esc=back)".to_string()
+        } else {
+            format!("Trash ({}, Esc=back)", app.keymap.hint(Context::Trash))
+        };
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_symbol("➤ ");
+
+        self.state.select(Some(self.selected));
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn handle(&mut self, action: &Action, app: &mut App) {
+        if !self.focus {
+            return;
+        }
+        let len = app.note_client.trashed_notes().unwrap_or_default().len();
+        match action {
+            Action::Up if self.selected > 0 => self.selected -= 1,
+            Action::Down if self.selected + 1 < len => self.selected += 1,
+            _ => {}
+        }
+    }
+
+    fn focused(&self) -> bool {
+        self.focus
+    }
+    fn set_focus(&mut self, focus: bool) {
+        self.focus = focus;
+    }
+}
@@ -1,7 +1,13 @@
 // src/config.rs
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
 
+use crate::db::SortOrder;
+use crate::export::Format;
+use crate::highlight::DEFAULT_THEME;
+use crate::keymap::KeyList;
 use config::{Config, ConfigError, File};
 use serde::Deserialize;
 
@@ -11,9 +17,112 @@ pub struct StoreSettings {
     pub name: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct TrashSettings {
+    /// Days a deleted note stays in the trash before it is purged; 0 keeps it forever
+    pub retention_days: u32,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct PreviewSettings {
+    /// syntect theme used for fenced code blocks
+    pub theme: String,
+}
+
+impl Default for PreviewSettings {
+    fn default() -> Self {
+        Self {
+            theme: DEFAULT_THEME.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+    /// Directory the list view's export key writes into
+    pub dir: String,
+    pub format: Format,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            dir: "export".to_string(),
+            format: Format::Markdown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    /// The list on the left, the preview on the right
+    Horizontal,
+    /// The list above the preview
+    Vertical,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LayoutSettings {
+    /// Show a live preview of the selected note next to the list
+    pub split: bool,
+    /// Percentage of the screen given to the list, kept between 10 and 90
+    pub ratio: u16,
+    pub orientation: Orientation,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            split: true,
+            ratio: 40,
+            orientation: Orientation::Horizontal,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct EditorSettings {
+    /// Vim-style normal, insert and visual modes in the built-in editor
+    pub vim: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ListSettings {
+    /// Order of the note list, changed with the sort key
+    pub sort: SortOrder,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     pub store: StoreSettings,
+    #[serde(default)]
+    pub trash: TrashSettings,
+    #[serde(default)]
+    pub preview: PreviewSettings,
+    #[serde(default)]
+    pub export: ExportSettings,
+    #[serde(default)]
+    pub editor: EditorSettings,
+    #[serde(default)]
+    pub layout: LayoutSettings,
+    #[serde(default)]
+    pub list: ListSettings,
+    /// Action names mapped to key chords, replacing the default keys of those actions
+    #[serde(default)]
+    pub keys: HashMap<String, KeyList>,
 }
 
 impl Settings {
@@ -29,3 +138,43 @@ impl Settings {
         PathBuf::from(&self.store.path).join(&self.store.name)
     }
 }
+
+/// Sets `key` in `[section]` of the settings file at `path`, leaving the rest of the
+/// file, comments included, as it was
+pub fn store(path: &Path, section: &str, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)?;
+    let mut doc: toml_edit::DocumentMut = text.parse()?;
+    let table = doc
+        .entry(section)
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or_else(|| format!("[{}] is not a table", section))?;
+    // assigning in place keeps the comments above an existing key
+    match table.get_mut(key) {
+        Some(item) => *item = toml_edit::value(value),
+        None => {
+            table.insert(key, toml_edit::value(value));
+        }
+    }
+    std::fs::write(path, doc.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_keeps_the_rest_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Settings.toml");
+        let text = "# where notes live\n[store]\npath = \"data\"\n\n[list]\n# newest first\nsort = \"created\"\n";
+        std::fs::write(&path, text).unwrap();
+        store(&path, "list", "sort", "title").unwrap();
+        store(&path, "layout", "split", "yes").unwrap();
+        let stored = std::fs::read_to_string(&path).unwrap();
+        assert!(stored.starts_with("# where notes live\n[store]\npath = \"data\"\n"));
+        assert!(stored.contains("# newest first\nsort = \"title\"\n"));
+        assert!(stored.contains("[layout]\nsplit = \"yes\"\n"));
+    }
+}
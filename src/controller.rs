@@ -1,102 +1,202 @@
 // src/controller.rs
-use crate::models::note::Note;
+use crate::models::{
+    note::{Note, parse_tags},
+    notebook::Notebook,
+};
 use crate::{
+    TerminalCleanupGuard,
     app::{App, Mode},
     components::{
-        component::Component, confirm_dialog::ConfirmDialog, edit_view::EditView,
-        list_view::ListView, markdown_view::MarkdownView,
+        component::Component,
+        confirm_dialog::ConfirmDialog,
+        edit_view::EditView,
+        history_view::HistoryView,
+        list_view::ListView,
+        markdown_view::MarkdownView,
+        notebook_tree::NotebookTree,
+        search_bar::SearchBar,
+        tag_sidebar::{SIDEBAR_WIDTH, TagSidebar},
+        task_view::TaskView,
+        trash_view::TrashView,
     },
-    input::{poll_action, Action},
+    config::{self, Orientation},
+    db::SortOrder,
+    editor, export,
+    input::{Action, poll_action},
+    keymap::Context,
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Rect},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{error::Error, io::Stdout};
+use std::{error::Error, fs, io::Stdout};
 
 // Define the states of the application
 #[derive(Debug, PartialEq)]
 enum State {
     List,
+    Search,
+    Tags,
+    Notebooks,
     Edit,
     Preview,
+    History,
+    Trash,
+    Tasks,
     ConfirmDelete,
     ConfirmDiscard,
+    ConfirmRestore,
+    ConfirmPurge,
+    ConfirmRelink,
+}
+
+impl State {
+    // Which bindings of the keymap apply
+    fn context(&self) -> Context {
+        match self {
+            State::List => Context::List,
+            State::Edit => Context::Editor,
+            State::Trash => Context::Trash,
+            State::Preview | State::Tasks => Context::Preview,
+            State::ConfirmDelete
+            | State::ConfirmDiscard
+            | State::ConfirmRestore
+            | State::ConfirmPurge
+            | State::ConfirmRelink => Context::Confirm,
+            State::Notebooks => Context::Notebooks,
+            State::Search | State::Tags | State::History => Context::Plain,
+        }
+    }
+}
+
+// Every screen component, shared by the state handlers
+struct Views {
+    list: ListView,
+    edit: EditView,
+    preview: MarkdownView,
+    // the live preview next to the list
+    side: MarkdownView,
+    search: SearchBar,
+    tags: TagSidebar,
+    notebooks: NotebookTree,
+    history: HistoryView,
+    trash: TrashView,
+    tasks: TaskView,
+    confirm_discarding_changes: ConfirmDialog,
+    confirm_deleting_changes: ConfirmDialog,
+    confirm_restoring_revision: ConfirmDialog,
+    confirm_purging_note: ConfirmDialog,
+    // asked after a rename, with the number of links in the prompt
+    confirm_updating_links: ConfirmDialog,
+}
+
+impl Views {
+    fn new() -> Self {
+        Self {
+            list: ListView::new(),
+            edit: EditView::new(),
+            preview: MarkdownView::new(),
+            side: MarkdownView::for_selection(),
+            search: SearchBar::new(),
+            tags: TagSidebar::new(),
+            notebooks: NotebookTree::new(),
+            history: HistoryView::new(),
+            trash: TrashView::new(),
+            tasks: TaskView::new(),
+            confirm_discarding_changes: ConfirmDialog::new("Discard changes?"),
+            confirm_deleting_changes: ConfirmDialog::new("Move note to trash?"),
+            confirm_restoring_revision: ConfirmDialog::new("Restore this revision?"),
+            confirm_purging_note: ConfirmDialog::new("Delete note permanently?"),
+            confirm_updating_links: ConfirmDialog::new("Update links?"),
+        }
+    }
 }
 
 pub fn run(
     app: &mut App,
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
 ) -> Result<(), Box<dyn Error>> {
-    let mut list = ListView::new();
-    let mut edit = EditView::new();
-    let mut preview = MarkdownView::new();
-    let mut confirm_discarding_changes = ConfirmDialog::new("Discard changes?");
-    let mut confirm_deleting_changes = ConfirmDialog::new("Delete note?");
+    let mut views = Views::new();
 
     let mut state = State::List;
-    list.set_focus(true);
+    views.list.set_focus(true);
 
     let result = loop {
+        if app.redraw {
+            terminal.clear()?;
+            app.redraw = false;
+        }
         terminal.draw(|f| {
             let area = f.area();
             match state {
-                State::List => list.render(f, area, app),
-                State::Edit => edit.render(f, area, app),
-                State::Preview => preview.render(f, area, app),
-                State::ConfirmDelete => confirm_deleting_changes.render(f, area, app),
-                State::ConfirmDiscard => confirm_discarding_changes.render(f, area, app),
+                State::List | State::Search | State::Tags | State::Notebooks => {
+                    render_list_screen(f, area, app, &mut views, &state)
+                }
+                State::Edit => views.edit.render(f, area, app),
+                State::Preview => views.preview.render(f, area, app),
+                State::History => views.history.render(f, area, app),
+                State::Trash => views.trash.render(f, area, app),
+                State::Tasks => views.tasks.render(f, area, app),
+                State::ConfirmDelete => views.confirm_deleting_changes.render(f, area, app),
+                State::ConfirmDiscard => views.confirm_discarding_changes.render(f, area, app),
+                State::ConfirmRestore => views.confirm_restoring_revision.render(f, area, app),
+                State::ConfirmPurge => views.confirm_purging_note.render(f, area, app),
+                State::ConfirmRelink => views.confirm_updating_links.render(f, area, app),
             };
         })?;
 
-        if let Some(action) = poll_action() {
-            match state {
-                State::List => {
-                    if let Err(e) = handle_list_state(
-                        app,
-                        &mut list,
-                        &mut edit,
-                        &mut preview,
-                        &mut confirm_deleting_changes,
-                        action,
-                        &mut state,
-                    ) {
-                        break Err(e);
-                    }
+        // a notebook name takes every key as text
+        let context = if views.notebooks.naming() {
+            Context::Plain
+        } else {
+            state.context()
+        };
+        if let Some(action) = poll_action(&app.keymap, context) {
+            let handled = match state {
+                State::List => handle_list_state(app, &mut views, action, &mut state),
+                State::Search => {
+                    handle_search_state(app, &mut views, action, &mut state);
+                    Ok(())
                 }
-                State::Edit => {
-                    if let Err(e) = handle_edit_state(
-                        app,
-                        &mut edit,
-                        &mut list,
-                        &mut confirm_discarding_changes,
-                        action,
-                        &mut state,
-                    ) {
-                        break Err(e);
-                    }
+                State::Tags => {
+                    handle_tags_state(app, &mut views, action, &mut state);
+                    Ok(())
                 }
+                State::Notebooks => handle_notebooks_state(app, &mut views, action, &mut state),
+                State::Edit => handle_edit_state(app, &mut views, action, &mut state),
                 State::Preview => {
-                    handle_preview_state(app, &mut preview, &mut list, action, &mut state);
+                    handle_preview_state(app, &mut views, action, &mut state);
+                    Ok(())
+                }
+                State::History => {
+                    handle_history_state(app, &mut views, action, &mut state);
+                    Ok(())
                 }
                 State::ConfirmDelete => {
-                    if let Err(e) = handle_confirm_delete_state(
-                        app,
-                        &mut confirm_deleting_changes,
-                        &mut list,
-                        action,
-                        &mut state,
-                    ) {
-                        break Err(e);
-                    }
+                    handle_confirm_delete_state(app, &mut views, action, &mut state)
+                }
+                State::ConfirmRestore => {
+                    handle_confirm_restore_state(app, &mut views, action, &mut state)
+                }
+                State::Trash => handle_trash_state(app, &mut views, action, &mut state),
+                State::Tasks => {
+                    handle_tasks_state(app, &mut views, action, &mut state);
+                    Ok(())
+                }
+                State::ConfirmPurge => {
+                    handle_confirm_purge_state(app, &mut views, action, &mut state)
                 }
                 State::ConfirmDiscard => {
-                    handle_confirm_discard_state(
-                        app,
-                        &mut confirm_discarding_changes,
-                        &mut edit,
-                        &mut list,
-                        action,
-                        &mut state,
-                    );
+                    handle_confirm_discard_state(app, &mut views, action, &mut state);
+                    Ok(())
                 }
+                State::ConfirmRelink => {
+                    handle_confirm_relink_state(app, &mut views, action, &mut state)
+                }
+            };
+            if let Err(e) = handled {
+                break Err(e);
             }
         }
     };
@@ -104,56 +204,331 @@ pub fn run(
     Ok(())
 }
 
+// The note list, with the notebook and tag sidebar when either exists and the search
+// prompt while searching
+fn render_list_screen(f: &mut Frame, area: Rect, app: &App, views: &mut Views, state: &State) {
+    let [list_area, prompt] = if *state == State::Search {
+        Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(area)
+    } else {
+        [area, Rect::default()]
+    };
+    let has_tags = !app.tags_with_counts().is_empty() || *state == State::Tags;
+    let has_notebooks = !app.notebook_tree().is_empty() || *state == State::Notebooks;
+    let list_area = if has_tags || has_notebooks {
+        let [sidebar, rest] =
+            Layout::horizontal([Constraint::Length(SIDEBAR_WIDTH), Constraint::Min(1)])
+                .areas(list_area);
+        match (has_notebooks, has_tags) {
+            (true, true) => {
+                let [top, bottom] =
+                    Layout::vertical([Constraint::Percentage(50); 2]).areas(sidebar);
+                views.notebooks.render(f, top, app);
+                views.tags.render(f, bottom, app);
+            }
+            (true, false) => views.notebooks.render(f, sidebar, app),
+            _ => views.tags.render(f, sidebar, app),
+        }
+        rest
+    } else {
+        list_area
+    };
+    let list_area = if app.layout.split {
+        let ratio = app.layout.ratio.clamp(10, 90);
+        let constraints = [Constraint::Percentage(ratio), Constraint::Min(1)];
+        let [list, side] = match app.layout.orientation {
+            Orientation::Horizontal => Layout::horizontal(constraints).areas(list_area),
+            Orientation::Vertical => Layout::vertical(constraints).areas(list_area),
+        };
+        views.side.render(f, side, app);
+        list
+    } else {
+        list_area
+    };
+    views.list.render(f, list_area, app);
+    if *state == State::Search {
+        views.search.render(f, prompt, app);
+    }
+}
+
 fn handle_list_state(
     app: &mut App,
-    list: &mut ListView,
-    edit: &mut EditView,
-    preview: &mut MarkdownView,
-    confirm_deleting_changes: &mut ConfirmDialog,
+    views: &mut Views,
     action: Action,
     state: &mut State,
 ) -> Result<(), Box<dyn Error>> {
+    // undo is only offered right after deleting
+    let last_trashed = app.last_trashed.take();
+    app.status = None;
+    if views.side.focused() {
+        match action {
+            Action::SwitchFocus | Action::Esc => {
+                views.side.set_focus(false);
+                views.list.set_focus(true);
+                return Ok(());
+            }
+            Action::Up
+            | Action::Down
+            | Action::PageUp
+            | Action::PageDown
+            | Action::Home
+            | Action::End
+            | Action::ScrollUp
+            | Action::ScrollDown => {
+                views.side.handle(&action, app);
+                return Ok(());
+            }
+            // anything else is meant for the list, which takes the focus back
+            _ => {
+                views.side.set_focus(false);
+                views.list.set_focus(true);
+            }
+        }
+    }
     match action {
-        Action::Char('q') => {
+        Action::Quit => {
             return Err("User requested quit".into());
         }
-        Action::Char('a') => {
-            start_add_note(app, list, edit, state);
+        Action::Add => {
+            start_add_note(app, views, state);
+        }
+        Action::Edit => {
+            start_edit_note(app, views, state)?;
+        }
+        Action::ExternalEdit => {
+            edit_in_external_editor(app)?;
+        }
+        Action::DeleteNote => {
+            start_delete_note(app, views, state)?;
+        }
+        Action::Preview => {
+            start_preview_note(app, views, state)?;
+        }
+        Action::Search => {
+            start_search(app, views, state);
+        }
+        Action::Tags => {
+            start_tag_filter(app, views, state);
+        }
+        Action::Notebooks => {
+            views.notebooks.start_filtering(app);
+            start_notebooks(views, state);
+        }
+        Action::MoveToNotebook if app.selected_note().is_some() => {
+            views.notebooks.start_moving(app);
+            start_notebooks(views, state);
+        }
+        Action::History => {
+            start_history(app, views, state)?;
+        }
+        Action::Trash => {
+            start_trash(app, views, state);
+        }
+        Action::OpenTasks => {
+            start_tasks(app, views, state);
+        }
+        Action::Export => {
+            export_notes(app)?;
+        }
+        Action::Sort => {
+            cycle_sort(app);
+        }
+        Action::MoveUp | Action::MoveDown => {
+            move_selected_note(app, action == Action::MoveUp)?;
+        }
+        Action::Pin => {
+            if let Some(note) = app.selected_note() {
+                app.note_client.set_pinned(note.id, !note.pinned)?;
+                // the note moves into or out of the pinned group; keep it selected
+                app.select(note.id);
+            }
+        }
+        Action::Favorite => {
+            if let Some(note) = app.selected_note() {
+                app.note_client.set_favorite(note.id, !note.favorite)?;
+            }
+        }
+        Action::Favorites => {
+            app.favorites = !app.favorites;
+            app.selected = 0;
+        }
+        Action::SwitchFocus if app.layout.split => {
+            views.list.set_focus(false);
+            views.side.set_focus(true);
+        }
+        Action::UndoDelete if let Some(id) = last_trashed => {
+            app.note_client.restore_note(id)?;
         }
-        Action::Char('e') => {
-            start_edit_note(app, list, edit, state)?;
+        Action::Esc if app.query.is_some() => {
+            app.query = None;
+            app.selected = 0;
         }
-        Action::Char('d') => {
-            start_delete_note(app, list, confirm_deleting_changes, state)?;
+        Action::Esc if app.tag.is_some() || app.favorites || app.notebook.is_some() => {
+            app.tag = None;
+            app.favorites = false;
+            app.notebook = None;
+            app.selected = 0;
+        }
+        other => {
+            views.list.handle(&other, app);
+            if views.list.take_open() {
+                start_preview_note(app, views, state)?;
+            }
         }
-        Action::Char('p') => {
-            start_preview_note(app, list, preview, state)?;
+    }
+    Ok(())
+}
+
+// Switches to the next sort order and remembers it in the settings file
+fn cycle_sort(app: &mut App) {
+    app.sort = app.sort.next();
+    app.selected = 0;
+    if let Err(e) = config::store(&app.settings_path, "list", "sort", app.sort.name()) {
+        app.status = Some(format!("sort order not saved: {}", e));
+    }
+}
+
+// Moves the selected note one place up or down in the manual order
+fn move_selected_note(app: &mut App, up: bool) -> Result<(), Box<dyn Error>> {
+    if app.sort != SortOrder::Manual
+        || app.tag.is_some()
+        || app.favorites
+        || app.notebook.is_some()
+        || app.active_query().is_some()
+    {
+        app.status = Some(format!(
+            "notes move in the unfiltered manual order, {}:sort",
+            app.keymap.key(Action::Sort)
+        ));
+        return Ok(());
+    }
+    let Some(note) = app.selected_note() else {
+        return Ok(());
+    };
+    if app.note_client.move_note(note.id, up)? {
+        if up {
+            app.selected -= 1;
+        } else {
+            app.selected += 1;
         }
-        other => list.handle(&other, app),
     }
     Ok(())
 }
 
+fn handle_search_state(app: &mut App, views: &mut Views, action: Action, state: &mut State) {
+    match action {
+        Action::Enter => {
+            // keep the filter applied to the list
+            views.search.set_focus(false);
+            *state = State::List;
+        }
+        Action::Esc => {
+            app.query = None;
+            app.selected = 0;
+            views.search.set_focus(false);
+            *state = State::List;
+        }
+        // the list stays focused underneath for Up/Down
+        other => {
+            views.search.handle(&other, app);
+            views.list.handle(&other, app);
+            // notes open once the search prompt is closed
+            views.list.take_open();
+        }
+    }
+}
+
+fn handle_tags_state(app: &mut App, views: &mut Views, action: Action, state: &mut State) {
+    match action {
+        Action::Enter => {
+            app.tag = views.tags.selected_tag(app);
+            app.selected = 0;
+        }
+        Action::Esc => {}
+        other => {
+            views.tags.handle(&other, app);
+            return;
+        }
+    }
+    views.tags.set_focus(false);
+    views.list.set_focus(true);
+    *state = State::List;
+}
+
+fn handle_notebooks_state(
+    app: &mut App,
+    views: &mut Views,
+    action: Action,
+    state: &mut State,
+) -> Result<(), Box<dyn Error>> {
+    if views.notebooks.naming() {
+        match action {
+            Action::Enter => {
+                let name = views.notebooks.take_name().unwrap_or_default();
+                let name = name.trim();
+                if !name.is_empty() {
+                    let parent = views.notebooks.selected_notebook(app);
+                    let notebook = Notebook::new(name, parent);
+                    match app.note_client.add_notebook(&notebook) {
+                        Ok(_) => views.notebooks.highlight(app, Some(notebook.id)),
+                        Err(e) => app.status = Some(format!("notebook not created: {}", e)),
+                    }
+                }
+            }
+            Action::Esc => {
+                views.notebooks.take_name();
+            }
+            other => views.notebooks.handle(&other, app),
+        }
+        return Ok(());
+    }
+    match action {
+        Action::Enter => {
+            let notebook = views.notebooks.selected_notebook(app);
+            if views.notebooks.moving() {
+                if let Some(note) = app.selected_note() {
+                    app.note_client.set_notebook(note.id, notebook)?;
+                    // the note may leave a filtered list; otherwise keep it selected
+                    app.select(note.id);
+                }
+            } else {
+                app.notebook = notebook;
+                app.selected = 0;
+            }
+        }
+        Action::Esc => {}
+        Action::NewNotebook => {
+            views.notebooks.start_naming();
+            return Ok(());
+        }
+        other => {
+            views.notebooks.handle(&other, app);
+            return Ok(());
+        }
+    }
+    views.notebooks.set_focus(false);
+    views.list.set_focus(true);
+    *state = State::List;
+    Ok(())
+}
+
 fn handle_edit_state(
     app: &mut App,
-    edit: &mut EditView,
-    list: &mut ListView,
-    confirm_discarding_changes: &mut ConfirmDialog,
+    views: &mut Views,
     action: Action,
     state: &mut State,
 ) -> Result<(), Box<dyn Error>> {
     match action {
         Action::Save => {
-            save_changes(app, edit, list, state)?;
+            save_changes(app, views, state)?;
         }
-        Action::Esc => {
-            discard_changes(app, edit, confirm_discarding_changes, state);
+        Action::Esc if !views.edit.captures_esc(app) => {
+            discard_changes(app, views, state);
         }
         other => {
-            edit.handle(&other, app);
+            views.edit.handle(&other, app);
             if matches!(app.mode, Mode::List) {
-                edit.set_focus(false);
-                list.set_focus(true);
+                views.edit.set_focus(false);
+                views.list.set_focus(true);
                 *state = State::List;
             }
         }
@@ -161,37 +536,166 @@ fn handle_edit_state(
     Ok(())
 }
 
-fn handle_preview_state(
+fn handle_preview_state(app: &mut App, views: &mut Views, action: Action, state: &mut State) {
+    views.preview.handle(&action, app);
+    if let Action::Esc = action {
+        views.preview.set_focus(false);
+        app.mode = Mode::List;
+        if views.tasks.take_previewing() {
+            start_tasks(app, views, state);
+        } else {
+            views.list.set_focus(true);
+            *state = State::List;
+        }
+    }
+}
+
+fn handle_tasks_state(app: &mut App, views: &mut Views, action: Action, state: &mut State) {
+    match action {
+        Action::Enter => {
+            if let Some(task) = views.tasks.selected_task(app) {
+                views.preview.open_at_task(task.note, task.index, app);
+                views.tasks.start_preview();
+                app.mode = Mode::EditContent;
+                views.tasks.set_focus(false);
+                views.preview.set_focus(true);
+                *state = State::Preview;
+            }
+        }
+        Action::Esc => {
+            views.tasks.set_focus(false);
+            views.list.set_focus(true);
+            *state = State::List;
+        }
+        other => views.tasks.handle(&other, app),
+    }
+}
+
+fn handle_history_state(app: &mut App, views: &mut Views, action: Action, state: &mut State) {
+    match action {
+        Action::Enter if views.history.selected_revision().is_some() => {
+            views.history.set_focus(false);
+            views.confirm_restoring_revision.set_focus(true);
+            *state = State::ConfirmRestore;
+        }
+        Action::Esc => {
+            views.history.set_focus(false);
+            views.list.set_focus(true);
+            *state = State::List;
+        }
+        other => views.history.handle(&other, app),
+    }
+}
+
+fn handle_confirm_restore_state(
     app: &mut App,
-    preview: &mut MarkdownView,
-    list: &mut ListView,
+    views: &mut Views,
     action: Action,
     state: &mut State,
-) {
-    preview.handle(&action, app);
-    if let Action::Esc = action {
-        preview.set_focus(false);
-        app.mode = Mode::List;
-        list.set_focus(true);
+) -> Result<(), Box<dyn Error>> {
+    views.confirm_restoring_revision.handle(&action, app);
+    if let Some(ok) = views.confirm_restoring_revision.take_result() {
+        views.confirm_restoring_revision.set_focus(false);
+        if ok {
+            restore_revision(app, views)?;
+            views.list.set_focus(true);
+            *state = State::List;
+        } else {
+            views.history.set_focus(true);
+            *state = State::History;
+        }
+    }
+    Ok(())
+}
+
+fn handle_trash_state(
+    app: &mut App,
+    views: &mut Views,
+    action: Action,
+    state: &mut State,
+) -> Result<(), Box<dyn Error>> {
+    match action {
+        Action::Restore => {
+            if let Some(n) = views.trash.selected_note(app) {
+                app.note_client.restore_note(n.id)?;
+                views.trash.clamp(app);
+            }
+        }
+        Action::Purge => {
+            if let Some(n) = views.trash.selected_note(app) {
+                app.delete_id = Some(n.id);
+                views.trash.set_focus(false);
+                views.confirm_purging_note.set_focus(true);
+                *state = State::ConfirmPurge;
+            }
+        }
+        Action::Esc => {
+            views.trash.set_focus(false);
+            views.list.set_focus(true);
+            *state = State::List;
+        }
+        other => views.trash.handle(&other, app),
+    }
+    Ok(())
+}
+
+fn handle_confirm_purge_state(
+    app: &mut App,
+    views: &mut Views,
+    action: Action,
+    state: &mut State,
+) -> Result<(), Box<dyn Error>> {
+    views.confirm_purging_note.handle(&action, app);
+    if let Some(ok) = views.confirm_purging_note.take_result() {
+        views.confirm_purging_note.set_focus(false);
+        if let Some(id) = app.delete_id.take()
+            && ok
+        {
+            app.note_client.delete_note(id)?;
+            views.trash.clamp(app);
+        }
+        views.trash.set_focus(true);
+        *state = State::Trash;
+    }
+    Ok(())
+}
+
+fn handle_confirm_relink_state(
+    app: &mut App,
+    views: &mut Views,
+    action: Action,
+    state: &mut State,
+) -> Result<(), Box<dyn Error>> {
+    views.confirm_updating_links.handle(&action, app);
+    if let Some(ok) = views.confirm_updating_links.take_result() {
+        views.confirm_updating_links.set_focus(false);
+        if let Some((id, old)) = app.renamed.take()
+            && ok
+            && let Some(note) = app.note_client.get_note_by_id(id)?
+        {
+            let updated = app.note_client.relink(&old, &note.title, id)?;
+            app.status = Some(format!("updated links in {} notes", updated));
+        }
+        views.list.set_focus(true);
         *state = State::List;
     }
+    Ok(())
 }
 
 fn handle_confirm_delete_state(
     app: &mut App,
-    confirm_deleting_changes: &mut ConfirmDialog,
-    list: &mut ListView,
+    views: &mut Views,
     action: Action,
     state: &mut State,
 ) -> Result<(), Box<dyn Error>> {
-    confirm_deleting_changes.handle(&action, app);
-    if let Some(ok) = confirm_deleting_changes.take_result() {
-        confirm_deleting_changes.set_focus(false);
+    views.confirm_deleting_changes.handle(&action, app);
+    if let Some(ok) = views.confirm_deleting_changes.take_result() {
+        views.confirm_deleting_changes.set_focus(false);
         if ok {
             delete_note(app)?;
         }
         app.mode = Mode::List;
-        list.set_focus(true);
+        views.list.set_focus(true);
         *state = State::List;
     }
     Ok(())
@@ -199,51 +703,66 @@ fn handle_confirm_delete_state(
 
 fn handle_confirm_discard_state(
     app: &mut App,
-    confirm_discarding_changes: &mut ConfirmDialog,
-    edit: &mut EditView,
-    list: &mut ListView,
+    views: &mut Views,
     action: Action,
     state: &mut State,
 ) {
-    confirm_discarding_changes.handle(&action, app);
-    if let Some(ok) = confirm_discarding_changes.take_result() {
-        confirm_discarding_changes.set_focus(false);
+    views.confirm_discarding_changes.handle(&action, app);
+    if let Some(ok) = views.confirm_discarding_changes.take_result() {
+        views.confirm_discarding_changes.set_focus(false);
         if ok {
             app.input.clear();
             app.buffer.clear();
             app.mode = Mode::List;
-            list.set_focus(true);
+            views.list.set_focus(true);
             *state = State::List;
         } else {
             app.mode = Mode::EditContent;
-            edit.set_focus(true);
+            views.edit.set_focus(true);
             *state = State::Edit;
         }
     }
 }
 
-fn start_add_note(app: &mut App, list: &mut ListView, edit: &mut EditView, state: &mut State) {
+fn start_add_note(app: &mut App, views: &mut Views, state: &mut State) {
     app.input.clear();
     app.buffer.clear();
     app.mode = Mode::AddTitle;
-    list.set_focus(false);
-    edit.set_focus(true);
+    views.list.set_focus(false);
+    views.edit.set_focus(true);
     *state = State::Edit;
 }
 
+fn start_search(app: &mut App, views: &mut Views, state: &mut State) {
+    app.query.get_or_insert_with(String::new);
+    views.search.set_focus(true);
+    *state = State::Search;
+}
+
+fn start_tag_filter(app: &mut App, views: &mut Views, state: &mut State) {
+    views.tags.sync(app);
+    views.list.set_focus(false);
+    views.tags.set_focus(true);
+    *state = State::Tags;
+}
+
+fn start_notebooks(views: &mut Views, state: &mut State) {
+    views.list.set_focus(false);
+    views.notebooks.set_focus(true);
+    *state = State::Notebooks;
+}
+
 fn start_edit_note(
     app: &mut App,
-    list: &mut ListView,
-    edit: &mut EditView,
+    views: &mut Views,
     state: &mut State,
 ) -> Result<(), Box<dyn Error>> {
-    let notes = app.note_client.get_all_notes().unwrap_or_default();
-    if let Some(n) = notes.get(app.selected) {
+    if let Some(n) = app.selected_note() {
         app.edit_id = Some(n.id);
-        app.input = n.title.clone();
+        app.input.set_text(n.title);
         app.mode = Mode::EditTitle;
-        list.set_focus(false);
-        edit.set_focus(true);
+        views.list.set_focus(false);
+        views.edit.set_focus(true);
         *state = State::Edit;
     }
     Ok(())
@@ -251,84 +770,163 @@ fn start_edit_note(
 
 fn start_preview_note(
     app: &mut App,
-    list: &mut ListView,
-    preview: &mut MarkdownView,
+    views: &mut Views,
     state: &mut State,
 ) -> Result<(), Box<dyn Error>> {
-    let notes = app.note_client.get_all_notes().unwrap_or_default();
-    if let Some(n) = notes.get(app.selected) {
-        app.buffer = n.title.clone();
-        app.input = n.content.clone();
+    if let Some(n) = app.selected_note() {
+        views.preview.open(n, app);
         app.mode = Mode::EditContent;
-        list.set_focus(false);
-        preview.set_focus(true);
+        views.list.set_focus(false);
+        views.preview.set_focus(true);
         *state = State::Preview;
     }
     Ok(())
 }
 
-fn start_delete_note(
+fn start_history(
     app: &mut App,
-    list: &mut ListView,
-    confirm_deleting_changes: &mut ConfirmDialog,
+    views: &mut Views,
     state: &mut State,
 ) -> Result<(), Box<dyn Error>> {
-    let notes = app.note_client.get_all_notes().unwrap_or_default();
-    if let Some(n) = notes.get(app.selected) {
-        app.delete_id = Some(n.id);
-        list.set_focus(false);
-        confirm_deleting_changes.set_focus(true);
-        *state = State::ConfirmDelete;
+    if let Some(n) = app.selected_note() {
+        let revisions = app.note_client.revisions(n.id)?;
+        views.history.open(n, revisions);
+        views.list.set_focus(false);
+        views.history.set_focus(true);
+        *state = State::History;
     }
     Ok(())
 }
 
-fn save_changes(
+fn start_trash(app: &mut App, views: &mut Views, state: &mut State) {
+    views.trash.clamp(app);
+    views.list.set_focus(false);
+    views.trash.set_focus(true);
+    *state = State::Trash;
+}
+
+fn start_tasks(app: &mut App, views: &mut Views, state: &mut State) {
+    views.tasks.clamp(app);
+    views.list.set_focus(false);
+    views.tasks.set_focus(true);
+    *state = State::Tasks;
+}
+
+fn start_delete_note(
     app: &mut App,
-    edit: &mut EditView,
-    list: &mut ListView,
+    views: &mut Views,
     state: &mut State,
 ) -> Result<(), Box<dyn Error>> {
+    if let Some(n) = app.selected_note() {
+        app.delete_id = Some(n.id);
+        views.list.set_focus(false);
+        views.confirm_deleting_changes.set_focus(true);
+        *state = State::ConfirmDelete;
+    }
+    Ok(())
+}
+
+fn save_changes(app: &mut App, views: &mut Views, state: &mut State) -> Result<(), Box<dyn Error>> {
     match app.mode {
         Mode::AddContent => {
-            let note = Note::new(&app.buffer, &app.input);
+            let note = Note::new(&app.buffer, app.input.as_str());
             app.note_client.add_note(&note)?;
         }
         Mode::EditContent => {
             if let Some(id) = app.edit_id {
                 let mut n = app.note_client.get_note_by_id(id)?.unwrap();
-                n.title = app.buffer.clone();
-                n.content = app.input.clone();
+                let old = std::mem::replace(&mut n.title, app.buffer.clone());
+                n.content = app.input.as_str().to_string();
+                n.tags = parse_tags(&n.content);
                 app.note_client.update_note(&mut n)?;
+                // links ignore case, so only a new spelling breaks them
+                let linked = if old.trim().eq_ignore_ascii_case(n.title.trim()) {
+                    0
+                } else {
+                    app.note_client.backlinks(&old, id)?.len()
+                };
+                if linked > 0 {
+                    views.confirm_updating_links.set_prompt(format!(
+                        "{} notes link to \"{}\".\nUpdate them to \"{}\"?",
+                        linked, old, n.title
+                    ));
+                    app.renamed = Some((id, old));
+                    app.mode = Mode::List;
+                    views.edit.set_focus(false);
+                    views.confirm_updating_links.set_focus(true);
+                    *state = State::ConfirmRelink;
+                    return Ok(());
+                }
             }
         }
         _ => {}
     }
     app.mode = Mode::List;
-    edit.set_focus(false);
-    list.set_focus(true);
+    views.edit.set_focus(false);
+    views.list.set_focus(true);
     *state = State::List;
     Ok(())
 }
 
-fn discard_changes(
-    app: &mut App,
-    edit: &mut EditView,
-    confirm_discarding_changes: &mut ConfirmDialog,
-    state: &mut State,
-) {
-    edit.set_focus(false);
-    confirm_discarding_changes.set_focus(true);
+fn discard_changes(_app: &mut App, views: &mut Views, state: &mut State) {
+    views.edit.set_focus(false);
+    views.confirm_discarding_changes.set_focus(true);
     *state = State::ConfirmDiscard;
 }
 
+// Hands the screen to $VISUAL/$EDITOR for the selected note and saves what comes back
+fn edit_in_external_editor(app: &mut App) -> Result<(), Box<dyn Error>> {
+    let Some(mut note) = app.selected_note() else {
+        return Ok(());
+    };
+    TerminalCleanupGuard::suspend()?;
+    let edited = editor::edit(&note.content);
+    TerminalCleanupGuard::resume()?;
+    app.redraw = true;
+
+    app.status = Some(match edited {
+        Ok(content) if content != note.content => {
+            note.content = content;
+            note.tags = parse_tags(&note.content);
+            app.note_client.update_note(&mut note)?;
+            "saved changes from the editor".to_string()
+        }
+        Ok(_) => "no changes".to_string(),
+        Err(e) => format!("editor failed: {}", e),
+    });
+    Ok(())
+}
+
+// A failed export is reported in the list title rather than ending the session
+fn export_notes(app: &mut App) -> Result<(), Box<dyn Error>> {
+    let notes = app.note_client.get_all_notes()?;
+    let path = export::default_path(&app.export_dir, app.export_format);
+    let result = fs::create_dir_all(&app.export_dir)
+        .map_err(Into::into)
+        .and_then(|_| export::export(&notes, app.export_format, &path));
+    app.status = Some(match result {
+        Ok(()) => format!("exported {} notes to {}", notes.len(), path.display()),
+        Err(e) => format!("export failed: {}", e),
+    });
+    Ok(())
+}
+
 fn delete_note(app: &mut App) -> Result<(), Box<dyn Error>> {
     if let Some(id) = app.delete_id.take() {
-        let _ = app.note_client.delete_note(id);
-        let len = app.note_client.get_all_notes().unwrap_or_default().len();
+        app.note_client.trash_note(id)?;
+        app.last_trashed = Some(id);
+        let len = app.note_count();
         if app.selected >= len && len > 0 {
             app.selected = len - 1;
         }
     }
     Ok(())
 }
+
+fn restore_revision(app: &mut App, views: &mut Views) -> Result<(), Box<dyn Error>> {
+    if let Some(revision) = views.history.selected_revision() {
+        app.note_client
+            .restore_revision(revision.note_id, revision.revision)?;
+    }
+    Ok(())
+}
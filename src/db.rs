@@ -1,30 +1,122 @@
 // src/db.rs
 
-use crate::models::note::Note;
-use chrono::Utc;
-use rusqlite::{params, Connection, OptionalExtension, Row};
+use crate::markdown::{replace_wiki_links, wiki_links};
+use crate::migrations::{self, MigrationError};
+use crate::models::{
+    note::{Note, parse_tags},
+    notebook::{self, Notebook, TreeEntry},
+    revision::Revision,
+    search::{MATCH_END, MATCH_START, SearchHit},
+};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::HashSet;
 use uuid::Uuid;
 
+// Columns read by `row_to_note`; queries must alias `notes` as `n`
+const NOTE_COLUMNS: &str = "n.id, n.title, n.content, n.created_at, n.updated_at, n.deleted_at,
+     (SELECT group_concat(t.name, ' ')
+      FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+      WHERE nt.note_id = n.id),
+     n.pinned, n.favorite, n.notebook_id";
+
+/// How the note list is ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Newest first
+    #[default]
+    Created,
+    /// Most recently changed first
+    Updated,
+    /// By title from A to Z, ignoring case
+    Title,
+    /// Arranged by hand
+    Manual,
+}
+
+impl SortOrder {
+    /// The order after this one when cycling through them
+    pub fn next(self) -> Self {
+        match self {
+            SortOrder::Created => SortOrder::Updated,
+            SortOrder::Updated => SortOrder::Title,
+            SortOrder::Title => SortOrder::Manual,
+            SortOrder::Manual => SortOrder::Created,
+        }
+    }
+
+    /// The name used in the settings
+    pub fn name(self) -> &'static str {
+        match self {
+            SortOrder::Created => "created",
+            SortOrder::Updated => "updated",
+            SortOrder::Title => "title",
+            SortOrder::Manual => "manual",
+        }
+    }
+
+    /// A short description for the list title
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Created => "created ↓",
+            SortOrder::Updated => "updated ↓",
+            SortOrder::Title => "title A–Z",
+            SortOrder::Manual => "manual",
+        }
+    }
+
+    // ORDER BY terms for `notes n`, pinned notes first and ties broken by id so every
+    // note has one place
+    fn order_by(self) -> &'static str {
+        match self {
+            SortOrder::Created => "n.pinned DESC, n.created_at DESC, n.id DESC",
+            SortOrder::Updated => "n.pinned DESC, n.updated_at DESC, n.id DESC",
+            SortOrder::Title => "n.pinned DESC, n.title COLLATE NOCASE, n.id",
+            SortOrder::Manual => "n.pinned DESC, n.position, n.id",
+        }
+    }
+
+    // The notes `n` ordered after the note `a`, for keyset pagination
+    fn after(self) -> String {
+        let rest = match self {
+            SortOrder::Created => "(n.created_at, n.id) < (a.created_at, a.id)",
+            SortOrder::Updated => "(n.updated_at, n.id) < (a.updated_at, a.id)",
+            SortOrder::Title => "(n.title COLLATE NOCASE, n.id) > (a.title, a.id)",
+            SortOrder::Manual => "(n.position, n.id) > (a.position, a.id)",
+        };
+        format!("(n.pinned < a.pinned OR n.pinned = a.pinned AND {})", rest)
+    }
+}
+
 pub struct NoteClient {
     connection: Connection,
+    // bumped by every write, so caches of query results can tell they are stale
+    generation: Cell<u64>,
 }
 
 impl NoteClient {
-    pub fn new(connection: Connection) -> Result<Self, rusqlite::Error> {
+    pub fn new(mut connection: Connection) -> Result<Self, MigrationError> {
         connection.execute_batch(
             "PRAGMA foreign_keys = ON;
-             PRAGMA journal_mode = WAL;
-             CREATE TABLE IF NOT EXISTS notes (
-                 id TEXT PRIMARY KEY,
-                 title TEXT NOT NULL,
-                 content TEXT NOT NULL,
-                 created_at TEXT NOT NULL,
-                 updated_at TEXT NOT NULL
-             );
-             CREATE INDEX IF NOT EXISTS idx_notes_created_at ON notes(created_at);
-            ",
+             PRAGMA journal_mode = WAL;",
         )?;
-        Ok(NoteClient { connection })
+        migrations::migrate(&mut connection)?;
+        Ok(NoteClient {
+            connection,
+            generation: Cell::new(0),
+        })
+    }
+
+    /// Changes whenever notes or tags were written through this client
+    pub fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    fn touch(&self) {
+        self.generation.set(self.generation.get() + 1);
     }
 
     fn row_to_note(row: &Row) -> rusqlite::Result<Note> {
@@ -34,75 +126,962 @@ impl NoteClient {
             content: row.get(2)?,
             created_at: row.get(3)?,
             updated_at: row.get(4)?,
+            deleted_at: row.get(5)?,
+            tags: Self::split_tags(row.get(6)?),
+            pinned: row.get(7)?,
+            favorite: row.get(8)?,
+            notebook_id: row.get(9)?,
         })
     }
 
+    fn split_tags(tags: Option<String>) -> Vec<String> {
+        let mut tags: Vec<String> = tags
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        tags.sort();
+        tags
+    }
+
     pub fn add_note(&self, note: &Note) -> Result<usize, rusqlite::Error> {
-        self.connection.execute(
-            "INSERT INTO notes (id, title, content, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        let tx = self.connection.unchecked_transaction()?;
+        let inserted = tx.execute(
+            // new notes go on top of the manual order
+            "INSERT INTO notes (id, title, content, created_at, updated_at, pinned, favorite,
+                                notebook_id, position)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8,
+                     (SELECT COALESCE(MIN(position), 0) - 1 FROM notes))",
             params![
                 note.id,
                 note.title,
                 note.content,
                 note.created_at,
-                note.updated_at
+                note.updated_at,
+                note.pinned,
+                note.favorite,
+                note.notebook_id
             ],
-        )
+        )?;
+        self.set_tags(note.id, &note.tags)?;
+        self.set_links(note.id, &note.content)?;
+        tx.commit()?;
+        self.touch();
+        Ok(inserted)
     }
 
     pub fn update_note(&self, note: &mut Note) -> Result<usize, rusqlite::Error> {
         note.updated_at = Utc::now();
-        self.connection.execute(
+        let tx = self.connection.unchecked_transaction()?;
+        let updated = tx.execute(
             "UPDATE notes SET title = ?1, content = ?2, updated_at = ?3 WHERE id = ?4",
             params![note.title, note.content, note.updated_at, note.id],
-        )
+        )?;
+        self.set_tags(note.id, &note.tags)?;
+        self.set_links(note.id, &note.content)?;
+        tx.commit()?;
+        self.touch();
+        Ok(updated)
     }
 
-    pub fn get_note_by_id(&self, id: Uuid) -> Result<Option<Note>, rusqlite::Error> {
+    /// Pins a note to the top of the list or unpins it; this is not an edit, so neither
+    /// the update time nor the history changes
+    pub fn set_pinned(&self, id: Uuid, pinned: bool) -> Result<usize, rusqlite::Error> {
+        let updated = self.connection.execute(
+            "UPDATE notes SET pinned = ?1 WHERE id = ?2",
+            params![pinned, id],
+        )?;
+        self.touch();
+        Ok(updated)
+    }
+
+    /// Marks a note as a favorite or clears the mark, like `set_pinned`
+    pub fn set_favorite(&self, id: Uuid, favorite: bool) -> Result<usize, rusqlite::Error> {
+        let updated = self.connection.execute(
+            "UPDATE notes SET favorite = ?1 WHERE id = ?2",
+            params![favorite, id],
+        )?;
+        self.touch();
+        Ok(updated)
+    }
+
+    /// Moves a note into a notebook, or out of every notebook with `None`
+    pub fn set_notebook(&self, id: Uuid, notebook: Option<Uuid>) -> Result<usize, rusqlite::Error> {
+        let updated = self.connection.execute(
+            "UPDATE notes SET notebook_id = ?1 WHERE id = ?2",
+            params![notebook, id],
+        )?;
+        self.touch();
+        Ok(updated)
+    }
+
+    pub fn add_notebook(&self, notebook: &Notebook) -> Result<usize, rusqlite::Error> {
+        let inserted = self.connection.execute(
+            "INSERT INTO notebooks (id, name, parent_id) VALUES (?1, ?2, ?3)",
+            params![notebook.id, notebook.name, notebook.parent_id],
+        )?;
+        self.touch();
+        Ok(inserted)
+    }
+
+    /// Deletes a notebook with the notebooks below it; their notes stay, outside any notebook
+    pub fn delete_notebook(&self, id: Uuid) -> Result<usize, rusqlite::Error> {
+        let deleted = self
+            .connection
+            .execute("DELETE FROM notebooks WHERE id = ?1", params![id])?;
+        self.touch();
+        Ok(deleted)
+    }
+
+    /// Every notebook in tree order, with note counts that include the notebooks below
+    pub fn notebook_tree(&self) -> Result<Vec<TreeEntry>, rusqlite::Error> {
         let mut stmt = self.connection.prepare(
-            "SELECT id, title, content, created_at, updated_at
-             FROM notes
-             WHERE id = ?1",
+            "SELECT b.id, b.name, b.parent_id,
+                    (SELECT COUNT(*) FROM notes n
+                     WHERE n.notebook_id = b.id AND n.deleted_at IS NULL)
+             FROM notebooks b",
         )?;
-        let note = stmt
-            .query_row(params![id], |row| Self::row_to_note(row))
-            .optional()?;
-        Ok(note)
+        let notebooks = stmt
+            .query_map([], |row| {
+                let notebook = Notebook {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    parent_id: row.get(2)?,
+                };
+                Ok((notebook, row.get(3)?))
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(notebook::tree(notebooks))
     }
 
-    pub fn get_all_notes(&self) -> Result<Vec<Note>, rusqlite::Error> {
+    /// The notebook at `path`, created along with any missing notebook above it; `None`
+    /// for an empty path, which stands for no notebook
+    pub fn create_notebook_path(&self, path: &str) -> Result<Option<Notebook>, rusqlite::Error> {
+        let tree = self.notebook_tree()?;
+        let mut parent: Option<Notebook> = None;
+        let mut walked = Vec::new();
+        for name in notebook::split_path(path) {
+            walked.push(name);
+            let notebook = match notebook::find(&tree, &walked.join("/")) {
+                Some(index) => tree[index].notebook.clone(),
+                None => {
+                    let notebook = Notebook::new(name, parent.as_ref().map(|p| p.id));
+                    self.add_notebook(&notebook)?;
+                    notebook
+                }
+            };
+            parent = Some(notebook);
+        }
+        Ok(parent)
+    }
+
+    /// Notes outside the trash in the given notebooks, in `sort` order
+    pub fn notes_in_notebooks(
+        &self,
+        ids: &[Uuid],
+        sort: SortOrder,
+    ) -> Result<Vec<Note>, rusqlite::Error> {
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE n.notebook_id IN ({placeholders}) AND n.deleted_at IS NULL
+             ORDER BY {}",
+            sort.order_by()
+        ))?;
+        let notes = stmt
+            .query_map(rusqlite::params_from_iter(ids), Self::row_to_note)?
+            .collect::<Result<_, _>>()?;
+        Ok(notes)
+    }
+
+    /// Previous versions of a note, newest first
+    pub fn revisions(&self, id: Uuid) -> Result<Vec<Revision>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(
+            "SELECT note_id, revision, title, content, updated_at
+             FROM note_revisions
+             WHERE note_id = ?1
+             ORDER BY revision DESC",
+        )?;
+        let revisions = stmt
+            .query_map(params![id], |row| {
+                Ok(Revision {
+                    note_id: row.get(0)?,
+                    revision: row.get(1)?,
+                    title: row.get(2)?,
+                    content: row.get(3)?,
+                    updated_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(revisions)
+    }
+
+    /// Puts the text of revision `rev` back into the note. The text being replaced is
+    /// itself kept as a new revision, so a restore can be undone the same way.
+    pub fn restore_revision(&self, id: Uuid, rev: u32) -> Result<Option<Note>, rusqlite::Error> {
+        let revision = self.connection.query_row(
+            "SELECT title, content FROM note_revisions WHERE note_id = ?1 AND revision = ?2",
+            params![id, rev],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        );
+        let (title, content) = match revision.optional()? {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        let Some(mut note) = self.get_note_by_id(id)? else {
+            return Ok(None);
+        };
+        note.title = title;
+        note.tags = parse_tags(&content);
+        note.content = content;
+        self.update_note(&mut note)?;
+        Ok(Some(note))
+    }
+
+    // Replaces the tags of a note and forgets tags no note uses anymore
+    fn set_tags(&self, id: Uuid, tags: &[String]) -> Result<(), rusqlite::Error> {
+        self.connection
+            .execute("DELETE FROM note_tags WHERE note_id = ?1", params![id])?;
+        for tag in tags {
+            self.connection.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                params![tag],
+            )?;
+            self.connection.execute(
+                "INSERT OR IGNORE INTO note_tags (note_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                params![id, tag],
+            )?;
+        }
+        self.connection.execute(
+            "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM note_tags)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn set_links(&self, id: Uuid, content: &str) -> Result<(), rusqlite::Error> {
+        self.connection
+            .execute("DELETE FROM note_links WHERE source_id = ?1", params![id])?;
+        for target in wiki_links(content) {
+            self.connection.execute(
+                "INSERT OR IGNORE INTO note_links (source_id, target) VALUES (?1, ?2)",
+                params![id, target],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Notes outside the trash, other than `except`, that link to `title`, by title
+    pub fn backlinks(&self, title: &str, except: Uuid) -> Result<Vec<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             JOIN note_links l ON l.source_id = n.id
+             WHERE l.target = ?1 AND n.id != ?2 AND n.deleted_at IS NULL
+             ORDER BY n.title COLLATE NOCASE, n.id"
+        ))?;
+        let notes = stmt
+            .query_map(params![title.trim(), except], Self::row_to_note)?
+            .collect::<Result<_, _>>()?;
+        Ok(notes)
+    }
+
+    /// The note a `[[title]]` link leads to: the newest one outside the trash with that
+    /// title, ignoring case
+    pub fn note_by_title(&self, title: &str) -> Result<Option<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE n.title = ?1 COLLATE NOCASE AND n.deleted_at IS NULL
+             ORDER BY n.created_at DESC
+             LIMIT 1"
+        ))?;
+        stmt.query_row(params![title.trim()], Self::row_to_note)
+            .optional()
+    }
+
+    /// Lowercased titles of the notes outside the trash, which links can lead to
+    pub fn link_titles(&self) -> Result<HashSet<String>, rusqlite::Error> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT title FROM notes WHERE deleted_at IS NULL")?;
+        let titles = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|title| title.map(|t| t.trim().to_ascii_lowercase()))
+            .collect::<Result<_, _>>()?;
+        Ok(titles)
+    }
+
+    /// Points the links to the note titled `old`, in notes other than `except`, to `new`;
+    /// returns how many notes changed
+    pub fn relink(&self, old: &str, new: &str, except: Uuid) -> Result<usize, rusqlite::Error> {
+        let notes = self.backlinks(old, except)?;
+        for mut note in notes.iter().cloned() {
+            note.content = replace_wiki_links(&note.content, old, new);
+            note.tags = parse_tags(&note.content);
+            self.update_note(&mut note)?;
+        }
+        Ok(notes.len())
+    }
+
+    pub fn tags_for(&self, id: Uuid) -> Result<Vec<String>, rusqlite::Error> {
         let mut stmt = self.connection.prepare(
-            "SELECT id, title, content, created_at, updated_at
-             FROM notes
-             ORDER BY created_at DESC",
+            "SELECT t.name
+             FROM tags t
+             JOIN note_tags nt ON nt.tag_id = t.id
+             WHERE nt.note_id = ?1
+             ORDER BY t.name",
         )?;
+        let tags = stmt
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(tags)
+    }
+
+    pub fn notes_with_tag(&self, tag: &str, sort: SortOrder) -> Result<Vec<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             JOIN note_tags nt ON nt.note_id = n.id
+             JOIN tags t ON t.id = nt.tag_id
+             WHERE t.name = ?1 AND n.deleted_at IS NULL
+             ORDER BY {}",
+            sort.order_by()
+        ))?;
         let notes = stmt
-            .query_map([], |row| Self::row_to_note(row))?
+            .query_map(params![tag], Self::row_to_note)?
             .collect::<Result<_, _>>()?;
         Ok(notes)
     }
 
-    // Starts from page 1
+    /// Notes outside the trash marked as favorites, in `sort` order
+    pub fn favorite_notes(&self, sort: SortOrder) -> Result<Vec<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE n.favorite AND n.deleted_at IS NULL
+             ORDER BY {}",
+            sort.order_by()
+        ))?;
+        let notes = stmt
+            .query_map([], Self::row_to_note)?
+            .collect::<Result<_, _>>()?;
+        Ok(notes)
+    }
+
+    /// Notes outside the trash that may hold unchecked `- [ ]` items, most recently
+    /// updated first. The text is only searched for a box; callers parse the items.
+    pub fn notes_with_open_tasks(&self) -> Result<Vec<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE n.content LIKE '%[ ]%' AND n.deleted_at IS NULL
+             ORDER BY n.updated_at DESC"
+        ))?;
+        let notes = stmt
+            .query_map([], Self::row_to_note)?
+            .collect::<Result<_, _>>()?;
+        Ok(notes)
+    }
+
+    /// Every tag on notes outside the trash with the number of notes carrying it, by name
+    pub fn all_tags_with_counts(&self) -> Result<Vec<(String, usize)>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(
+            "SELECT t.name, COUNT(nt.note_id)
+             FROM tags t
+             JOIN note_tags nt ON nt.tag_id = t.id
+             JOIN notes n ON n.id = nt.note_id
+             WHERE n.deleted_at IS NULL
+             GROUP BY t.id
+             ORDER BY t.name",
+        )?;
+        let tags = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(tags)
+    }
+
+    pub fn get_note_by_id(&self, id: Uuid) -> Result<Option<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE n.id = ?1"
+        ))?;
+        let note = stmt.query_row(params![id], Self::row_to_note).optional()?;
+        Ok(note)
+    }
+
+    /// Notes, trashed ones included, whose id starts with the given hex digits
+    pub fn notes_with_id_prefix(&self, prefix: &str) -> Result<Vec<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE substr(lower(hex(n.id)), 1, length(?1)) = lower(?1)
+             ORDER BY n.created_at DESC"
+        ))?;
+        let notes = stmt
+            .query_map(params![prefix], Self::row_to_note)?
+            .collect::<Result<_, _>>()?;
+        Ok(notes)
+    }
+
+    pub fn get_all_notes(&self) -> Result<Vec<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE n.deleted_at IS NULL
+             ORDER BY n.created_at DESC, n.id DESC"
+        ))?;
+        let notes = stmt
+            .query_map([], Self::row_to_note)?
+            .collect::<Result<_, _>>()?;
+        Ok(notes)
+    }
+
+    /// Number of notes outside the trash
+    pub fn count_notes(&self) -> Result<usize, rusqlite::Error> {
+        self.connection.query_row(
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Up to `per_page` notes in `sort` order, starting after `after`, the last note of
+    /// the previous page, or from the top when it is `None`
     pub fn get_notes_paginated(
         &self,
-        page: u32,
+        sort: SortOrder,
+        after: Option<&Note>,
         per_page: u32,
     ) -> Result<Vec<Note>, rusqlite::Error> {
-        let offset = per_page.saturating_mul(page.saturating_sub(1));
-        let mut stmt = self.connection.prepare(
-            "SELECT id, title, content, created_at, updated_at
-             FROM notes
-             ORDER BY created_at DESC
-             LIMIT ?1 OFFSET ?2",
+        let after_clause = match after {
+            Some(_) => format!("JOIN notes a ON a.id = ?1 WHERE {} AND", sort.after()),
+            None => String::from("WHERE"),
+        };
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n {after_clause} n.deleted_at IS NULL
+             ORDER BY {}
+             LIMIT ?2",
+            sort.order_by()
+        ))?;
+        let notes = stmt
+            .query_map(params![after.map(|n| n.id), per_page], Self::row_to_note)?
+            .collect::<Result<_, _>>()?;
+        Ok(notes)
+    }
+
+    /// Swaps a note with its neighbour in the manual order; false when it already is
+    /// at that end of the list, or of the pinned notes
+    pub fn move_note(&self, id: Uuid, up: bool) -> Result<bool, rusqlite::Error> {
+        let (before, direction) = if up { ("<", "DESC") } else { (">", "ASC") };
+        let tx = self.connection.unchecked_transaction()?;
+        let neighbour: Option<(Uuid, i64)> = tx
+            .query_row(
+                &format!(
+                    "SELECT n.id, n.position
+                     FROM notes n
+                     JOIN notes a ON a.id = ?1
+                     WHERE n.deleted_at IS NULL
+                       AND n.pinned = a.pinned
+                       AND (n.position, n.id) {before} (a.position, a.id)
+                     ORDER BY n.position {direction}, n.id {direction}
+                     LIMIT 1"
+                ),
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((other, other_position)) = neighbour else {
+            return Ok(false);
+        };
+        let position: i64 = tx.query_row(
+            "SELECT position FROM notes WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "UPDATE notes SET position = ?1 WHERE id = ?2",
+            params![other_position, id],
+        )?;
+        tx.execute(
+            "UPDATE notes SET position = ?1 WHERE id = ?2",
+            params![position, other],
+        )?;
+        tx.commit()?;
+        self.touch();
+        Ok(true)
+    }
+
+    /// Full-text search over titles and contents, best matches first.
+    /// `query` is free text typed by the user; every word is matched as a prefix.
+    pub fn search(&self, query: &str, limit: u32) -> Result<Vec<SearchHit>, rusqlite::Error> {
+        let Some(query) = Self::fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS},
+                    snippet(notes_fts, -1, ?2, ?3, '…', 12)
+             FROM notes_fts
+             JOIN notes n ON n.rowid = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND n.deleted_at IS NULL
+             ORDER BY rank
+             LIMIT ?4"
+        ))?;
+        let hits = stmt
+            .query_map(params![query, MATCH_START, MATCH_END, limit], |row| {
+                Ok(SearchHit {
+                    note: Self::row_to_note(row)?,
+                    snippet: row.get(10)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(hits)
+    }
+
+    // Quote every word so FTS5 operators typed by the user are taken literally
+    fn fts_query(input: &str) -> Option<String> {
+        let terms: Vec<String> = input
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect();
+        if terms.is_empty() {
+            None
+        } else {
+            Some(terms.join(" "))
+        }
+    }
+
+    /// Moves a note to the trash; it disappears from every listing but can be restored.
+    pub fn trash_note(&self, id: Uuid) -> Result<usize, rusqlite::Error> {
+        let trashed = self.connection.execute(
+            "UPDATE notes SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![Utc::now(), id],
+        )?;
+        self.touch();
+        Ok(trashed)
+    }
+
+    pub fn restore_note(&self, id: Uuid) -> Result<usize, rusqlite::Error> {
+        let restored = self.connection.execute(
+            "UPDATE notes SET deleted_at = NULL WHERE id = ?1",
+            params![id],
         )?;
+        self.touch();
+        Ok(restored)
+    }
+
+    /// Notes in the trash, most recently deleted first
+    pub fn trashed_notes(&self) -> Result<Vec<Note>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {NOTE_COLUMNS}
+             FROM notes n
+             WHERE n.deleted_at IS NOT NULL
+             ORDER BY n.deleted_at DESC"
+        ))?;
         let notes = stmt
-            .query_map(params![per_page, offset], |row| Self::row_to_note(row))?
+            .query_map([], Self::row_to_note)?
             .collect::<Result<_, _>>()?;
         Ok(notes)
     }
 
+    /// Permanently deletes notes that were trashed before `cutoff`
+    pub fn purge_trash(&self, cutoff: DateTime<Utc>) -> Result<usize, rusqlite::Error> {
+        let ids: Vec<Uuid> = self
+            .connection
+            .prepare("SELECT id FROM notes WHERE deleted_at < ?1")?
+            .query_map(params![cutoff], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        for id in &ids {
+            self.delete_note(*id)?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Permanently deletes a note along with its revisions
     pub fn delete_note(&self, id: Uuid) -> Result<usize, rusqlite::Error> {
-        self.connection
-            .execute("DELETE FROM notes WHERE id = ?1", params![id])
+        let tx = self.connection.unchecked_transaction()?;
+        let deleted = tx.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
+        tx.execute(
+            "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM note_tags)",
+            [],
+        )?;
+        tx.commit()?;
+        self.touch();
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::note::parse_tags;
+
+    fn client() -> NoteClient {
+        NoteClient::new(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_search_follows_inserts_updates_and_deletes() {
+        let client = client();
+        let mut note = Note::new("Groceries", "buy milk and eggs");
+        client.add_note(&note).unwrap();
+        client
+            .add_note(&Note::new("Work", "quarterly report"))
+            .unwrap();
+
+        let hits = client.search("mil", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].note.id, note.id);
+        assert!(
+            hits[0]
+                .snippet
+                .contains(&format!("{MATCH_START}milk{MATCH_END}"))
+        );
+
+        note.content = "buy bread".to_string();
+        client.update_note(&mut note).unwrap();
+        assert!(client.search("milk", 10).unwrap().is_empty());
+        assert_eq!(client.search("bread", 10).unwrap().len(), 1);
+
+        client.delete_note(note.id).unwrap();
+        assert!(client.search("bread", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_ranks_and_limits() {
+        let client = client();
+        client
+            .add_note(&Note::new("rust", "rust rust rust"))
+            .unwrap();
+        client
+            .add_note(&Note::new("misc", "some rust here"))
+            .unwrap();
+        client.add_note(&Note::new("other", "nothing")).unwrap();
+
+        let hits = client.search("rust", 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].note.title, "rust");
+        assert_eq!(client.search("rust", 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_treats_operators_literally() {
+        let client = client();
+        client
+            .add_note(&Note::new("quote", "say \"hi\" OR bye"))
+            .unwrap();
+
+        assert!(client.search("\"hi", 10).is_ok());
+        assert!(client.search("NOT (", 10).is_ok());
+        assert!(client.search("   ", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_revisions_and_restore() {
+        let client = client();
+        let mut note = Note::new("Draft", "first");
+        client.add_note(&note).unwrap();
+        assert!(client.revisions(note.id).unwrap().is_empty());
+
+        note.content = "second".to_string();
+        client.update_note(&mut note).unwrap();
+        // saving unchanged text does not add a revision
+        client.update_note(&mut note).unwrap();
+        note.title = "Final".to_string();
+        note.content = "third".to_string();
+        client.update_note(&mut note).unwrap();
+
+        let revisions = client.revisions(note.id).unwrap();
+        let texts: Vec<_> = revisions
+            .iter()
+            .map(|r| (r.revision, r.title.as_str(), r.content.as_str()))
+            .collect();
+        assert_eq!(texts, vec![(2, "Draft", "second"), (1, "Draft", "first")]);
+
+        let restored = client.restore_revision(note.id, 1).unwrap().unwrap();
+        assert_eq!(
+            (restored.title.as_str(), restored.content.as_str()),
+            ("Draft", "first")
+        );
+        let current = client.get_note_by_id(note.id).unwrap().unwrap();
+        assert_eq!(current.content, "first");
+        // the replaced text became revision 3
+        let latest = &client.revisions(note.id).unwrap()[0];
+        assert_eq!((latest.revision, latest.content.as_str()), (3, "third"));
+
+        assert!(client.restore_revision(note.id, 42).unwrap().is_none());
+        client.delete_note(note.id).unwrap();
+        assert!(client.revisions(note.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trash_restore_and_purge() {
+        let client = client();
+        let kept = Note::new("Kept", "stays #shared");
+        let trashed = Note::new("Trashed", "goes away #shared #gone");
+        client.add_note(&kept).unwrap();
+        client.add_note(&trashed).unwrap();
+
+        client.trash_note(trashed.id).unwrap();
+        let titles = |notes: Vec<Note>| notes.into_iter().map(|n| n.title).collect::<Vec<_>>();
+        assert_eq!(titles(client.get_all_notes().unwrap()), vec!["Kept"]);
+        assert_eq!(titles(client.trashed_notes().unwrap()), vec!["Trashed"]);
+        assert!(client.search("away", 10).unwrap().is_empty());
+        assert_eq!(
+            client.all_tags_with_counts().unwrap(),
+            vec![("shared".to_string(), 1)]
+        );
+        assert!(
+            client
+                .get_note_by_id(trashed.id)
+                .unwrap()
+                .unwrap()
+                .deleted_at
+                .is_some()
+        );
+
+        client.restore_note(trashed.id).unwrap();
+        assert_eq!(client.get_all_notes().unwrap().len(), 2);
+        assert!(client.trashed_notes().unwrap().is_empty());
+
+        client.trash_note(trashed.id).unwrap();
+        // trashed just now, so a cutoff in the past keeps it
+        assert_eq!(
+            client
+                .purge_trash(Utc::now() - chrono::Duration::days(1))
+                .unwrap(),
+            0
+        );
+        assert_eq!(client.purge_trash(Utc::now()).unwrap(), 1);
+        assert!(client.get_note_by_id(trashed.id).unwrap().is_none());
+        assert!(
+            client
+                .notes_with_tag("gone", SortOrder::Created)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_pages_follow_the_full_listing() {
+        let client = client();
+        let created = Utc::now();
+        for i in 0..7 {
+            let mut note = Note::new(&format!("Note {}", i), "");
+            // some share a creation time, which the id has to order
+            note.created_at = created - chrono::Duration::seconds(i / 2);
+            client.add_note(&note).unwrap();
+        }
+        let trashed = Note::new("Trashed", "");
+        client.add_note(&trashed).unwrap();
+        client.trash_note(trashed.id).unwrap();
+        assert_eq!(client.count_notes().unwrap(), 7);
+
+        let pages = |sort| {
+            let mut paged: Vec<Note> = Vec::new();
+            loop {
+                let page = client.get_notes_paginated(sort, paged.last(), 3).unwrap();
+                if page.is_empty() {
+                    return paged;
+                }
+                assert!(page.len() <= 3);
+                paged.extend(page);
+            }
+        };
+        assert_eq!(pages(SortOrder::Created), client.get_all_notes().unwrap());
+        assert_eq!(pages(SortOrder::Title).len(), 7);
+    }
+
+    #[test]
+    fn test_sort_orders() {
+        let client = client();
+        let created = Utc::now();
+        let mut notes = Vec::new();
+        for (i, title) in ["beta", "Alpha", "gamma"].into_iter().enumerate() {
+            let mut note = Note::new(title, "");
+            note.created_at = created + chrono::Duration::seconds(i as i64);
+            client.add_note(&note).unwrap();
+            notes.push(note);
+        }
+        client.update_note(&mut notes[0]).unwrap();
+        let titles = |sort| -> Vec<String> {
+            client
+                .get_notes_paginated(sort, None, 10)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.title)
+                .collect()
+        };
+        assert_eq!(titles(SortOrder::Created), ["gamma", "Alpha", "beta"]);
+        assert_eq!(titles(SortOrder::Updated), ["beta", "gamma", "Alpha"]);
+        assert_eq!(titles(SortOrder::Title), ["Alpha", "beta", "gamma"]);
+        // by hand, newest on top until moved
+        assert_eq!(titles(SortOrder::Manual), ["gamma", "Alpha", "beta"]);
+        assert!(client.move_note(notes[0].id, true).unwrap());
+        assert!(client.move_note(notes[0].id, true).unwrap());
+        assert!(!client.move_note(notes[0].id, true).unwrap());
+        assert!(client.move_note(notes[2].id, false).unwrap());
+        assert_eq!(titles(SortOrder::Manual), ["beta", "Alpha", "gamma"]);
+    }
+
+    #[test]
+    fn test_tags_are_stored_and_counted() {
+        let client = client();
+        let mut rust = Note::new("Rust", "learning #rust and #sqlite");
+        let sql = Note::new("SQL", "more #sqlite");
+        client.add_note(&rust).unwrap();
+        client.add_note(&sql).unwrap();
+
+        assert_eq!(client.tags_for(rust.id).unwrap(), vec!["rust", "sqlite"]);
+        assert_eq!(
+            client.get_note_by_id(rust.id).unwrap().unwrap().tags,
+            vec!["rust", "sqlite"]
+        );
+        assert_eq!(
+            client
+                .notes_with_tag("sqlite", SortOrder::Created)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            client.all_tags_with_counts().unwrap(),
+            vec![("rust".to_string(), 1), ("sqlite".to_string(), 2)]
+        );
+
+        rust.content = "no longer tagged".to_string();
+        rust.tags = parse_tags(&rust.content);
+        client.update_note(&mut rust).unwrap();
+        client.delete_note(sql.id).unwrap();
+        assert!(client.all_tags_with_counts().unwrap().is_empty());
+        assert!(
+            client
+                .notes_with_tag("sqlite", SortOrder::Created)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_notebooks() {
+        let client = client();
+        let projects = client
+            .create_notebook_path("Work/Projects")
+            .unwrap()
+            .unwrap();
+        let tree = client.notebook_tree().unwrap();
+        let work = tree[notebook::find(&tree, "work").unwrap()].notebook.id;
+        assert_eq!(projects.parent_id, Some(work));
+        // existing notebooks are reused, whatever the case
+        assert_eq!(
+            client.create_notebook_path("WORK/projects/").unwrap(),
+            Some(projects.clone())
+        );
+        assert_eq!(client.create_notebook_path(" / ").unwrap(), None);
+
+        let mut inside = Note::new("Inside", "");
+        inside.notebook_id = Some(projects.id);
+        client.add_note(&inside).unwrap();
+        let above = Note::new("Above", "");
+        client.add_note(&above).unwrap();
+        client.set_notebook(above.id, Some(work)).unwrap();
+        client.add_note(&Note::new("Loose", "")).unwrap();
+
+        let counts: Vec<(String, usize)> = client
+            .notebook_tree()
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.path, e.count))
+            .collect();
+        assert_eq!(
+            counts,
+            [("Work".to_string(), 2), ("Work/Projects".to_string(), 1)]
+        );
+        let titles = |ids: &[Uuid]| -> Vec<String> {
+            client
+                .notes_in_notebooks(ids, SortOrder::Title)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.title)
+                .collect()
+        };
+        assert_eq!(titles(&[work, projects.id]), ["Above", "Inside"]);
+
+        // the notebooks below go along, the notes stay
+        client.delete_notebook(work).unwrap();
+        assert!(client.notebook_tree().unwrap().is_empty());
+        assert_eq!(
+            client
+                .get_note_by_id(inside.id)
+                .unwrap()
+                .unwrap()
+                .notebook_id,
+            None
+        );
+    }
+
+    #[test]
+    fn test_links_follow_saves_and_renames() {
+        let client = client();
+        let target = Note::new("Plans", "");
+        client.add_note(&target).unwrap();
+        let mut source = Note::new("Diary", "see [[plans]] and [[Nowhere]]");
+        client.add_note(&source).unwrap();
+        let other = Note::new("Other", "[[Plans|the plan]]");
+        client.add_note(&other).unwrap();
+
+        let titles =
+            |notes: Vec<Note>| -> Vec<String> { notes.into_iter().map(|n| n.title).collect() };
+        assert_eq!(
+            titles(client.backlinks("PLANS", target.id).unwrap()),
+            ["Diary", "Other"]
+        );
+        assert_eq!(
+            client.note_by_title("plans").unwrap().map(|n| n.id),
+            Some(target.id)
+        );
+        assert!(client.note_by_title("Nowhere").unwrap().is_none());
+        assert!(client.link_titles().unwrap().contains("diary"));
+
+        // saving replaces the links, trashing hides them
+        source.content = "nothing here".to_string();
+        client.update_note(&mut source).unwrap();
+        client.trash_note(other.id).unwrap();
+        assert!(client.backlinks("Plans", target.id).unwrap().is_empty());
+        client.restore_note(other.id).unwrap();
+
+        assert_eq!(client.relink("Plans", "Weekly", target.id).unwrap(), 1);
+        let other = client.get_note_by_id(other.id).unwrap().unwrap();
+        assert_eq!(other.content, "[[Weekly|the plan]]");
+        assert_eq!(
+            titles(client.backlinks("weekly", target.id).unwrap()),
+            ["Other"]
+        );
+    }
+
+    #[test]
+    fn test_notes_with_open_tasks() {
+        let client = client();
+        let mut done = Note::new("Done", "- [x] all of it");
+        let open = Note::new("Open", "- [x] one\n- [ ] two");
+        let trashed = Note::new("Trashed", "- [ ] hidden");
+        for note in [&done, &open, &trashed] {
+            client.add_note(note).unwrap();
+        }
+        client.trash_note(trashed.id).unwrap();
+        let titles =
+            |notes: Vec<Note>| -> Vec<String> { notes.into_iter().map(|n| n.title).collect() };
+        assert_eq!(titles(client.notes_with_open_tasks().unwrap()), ["Open"]);
+
+        done.content = "- [x] all of it\n- [ ] one more".to_string();
+        client.update_note(&mut done).unwrap();
+        assert_eq!(
+            titles(client.notes_with_open_tasks().unwrap()),
+            ["Done", "Open"]
+        );
     }
 }
@@ -0,0 +1,73 @@
+// src/editor.rs
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process::Command;
+
+/// The user's editor: `$VISUAL`, then `$EDITOR`, then vi
+pub fn command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Lets the user change `text` in their editor and returns the saved result.
+pub fn edit(text: &str) -> Result<String, Box<dyn Error>> {
+    edit_with(&command(), text)
+}
+
+// `command` may carry arguments, e.g. "code --wait"
+fn edit_with(command: &str, text: &str) -> Result<String, Box<dyn Error>> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or("no editor configured")?;
+
+    let file = tempfile::Builder::new()
+        .prefix("terminote-")
+        .suffix(".md")
+        .tempfile()?;
+    // most editors end the file with a newline, which should not count as a change
+    let bare = !text.ends_with('\n');
+    fs::write(
+        file.path(),
+        if bare {
+            format!("{}\n", text)
+        } else {
+            text.to_string()
+        },
+    )?;
+
+    let status = Command::new(program)
+        .args(words)
+        .arg(file.path())
+        .status()
+        .map_err(|e| format!("could not start {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status).into());
+    }
+
+    // editors such as vim replace the file, so it is read back by path
+    let edited = fs::read_to_string(file.path())?;
+    Ok(match edited.strip_suffix('\n') {
+        Some(stripped) if bare => stripped.to_string(),
+        _ => edited,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_with() {
+        assert_eq!(
+            edit_with("sed -i s/milk/eggs/", "buy milk").unwrap(),
+            "buy eggs"
+        );
+        assert_eq!(edit_with("true", "unchanged\n").unwrap(), "unchanged\n");
+        assert!(edit_with("false", "x").is_err());
+        assert!(edit_with("no-such-editor-here", "x").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+// src/export.rs
+
+use crate::models::note::Note;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// A directory of `.md` files with YAML front matter
+    #[value(alias = "md")]
+    Markdown,
+    /// One JSON array of notes
+    Json,
+    /// One JSON note per line
+    Jsonl,
+    /// The Markdown files in a single tar archive
+    Tar,
+}
+
+/// Metadata at the top of an exported Markdown file, between `---` lines
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FrontMatter {
+    pub id: Uuid,
+    pub title: String,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Writes `notes` to `path`, a directory for Markdown and a file for the other formats.
+pub fn export(notes: &[Note], format: Format, path: &Path) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Markdown => {
+            fs::create_dir_all(path)?;
+            for (note, name) in notes.iter().zip(file_names(notes)) {
+                fs::write(path.join(name), to_markdown(note)?)?;
+            }
+        }
+        Format::Json => {
+            let mut out = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(&mut out, notes)?;
+            writeln!(out)?;
+            out.flush()?;
+        }
+        Format::Jsonl => {
+            let mut out = BufWriter::new(File::create(path)?);
+            for note in notes {
+                serde_json::to_writer(&mut out, note)?;
+                writeln!(out)?;
+            }
+            out.flush()?;
+        }
+        Format::Tar => {
+            let mut archive = tar::Builder::new(BufWriter::new(File::create(path)?));
+            for (note, name) in notes.iter().zip(file_names(notes)) {
+                let data = to_markdown(note)?;
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(note.updated_at.timestamp().max(0) as u64);
+                header.set_cksum();
+                archive.append_data(&mut header, name, data.as_bytes())?;
+            }
+            archive.into_inner()?.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// A fresh, timestamped export target inside `dir`
+pub fn default_path(dir: &Path, format: Format) -> PathBuf {
+    let stem = format!("terminote-{}", Utc::now().format("%Y%m%d-%H%M%S"));
+    let name = match format {
+        Format::Markdown => stem,
+        Format::Json => format!("{}.json", stem),
+        Format::Jsonl => format!("{}.jsonl", stem),
+        Format::Tar => format!("{}.tar", stem),
+    };
+    dir.join(name)
+}
+
+pub fn to_markdown(note: &Note) -> Result<String, serde_yaml::Error> {
+    let front = FrontMatter {
+        id: note.id,
+        title: note.title.clone(),
+        created: note.created_at,
+        updated: note.updated_at,
+        tags: note.tags.clone(),
+    };
+    // serde_yaml ends the mapping with a newline
+    Ok(format!(
+        "---\n{}---\n{}",
+        serde_yaml::to_string(&front)?,
+        note.content
+    ))
+}
+
+// One file name per note, from its title, made unique with a counter
+fn file_names(notes: &[Note]) -> Vec<String> {
+    let mut taken = HashSet::new();
+    notes
+        .iter()
+        .map(|note| {
+            let stem: String = note
+                .title
+                .chars()
+                .map(|c| {
+                    if c.is_control()
+                        || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+                    {
+                        '_'
+                    } else {
+                        c
+                    }
+                })
+                .take(80)
+                .collect();
+            let stem = match stem.trim().trim_start_matches('.') {
+                "" => "untitled".to_string(),
+                stem => stem.to_string(),
+            };
+            let mut name = format!("{}.md", stem);
+            let mut n = 2;
+            while !taken.insert(name.to_lowercase()) {
+                name = format!("{} ({}).md", stem, n);
+                n += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn notes() -> Vec<Note> {
+        vec![
+            Note::new("Shopping", "milk #errand\n"),
+            Note::new(
+                "Shopping",
+                "second list: with \"quotes\"\n---\nnot front matter",
+            ),
+            Note::new("a/b: c", ""),
+        ]
+    }
+
+    // The note a Markdown file describes
+    fn from_markdown(text: &str) -> Note {
+        let rest = text.strip_prefix("---\n").unwrap();
+        let (yaml, content) = rest.split_once("---\n").unwrap();
+        let front: FrontMatter = serde_yaml::from_str(yaml).unwrap();
+        Note {
+            id: front.id,
+            title: front.title,
+            content: content.to_string(),
+            created_at: front.created,
+            updated_at: front.updated,
+            deleted_at: None,
+            tags: front.tags,
+            pinned: false,
+            favorite: false,
+            notebook_id: None,
+        }
+    }
+
+    #[test]
+    fn test_file_names() {
+        assert_eq!(
+            file_names(&notes()),
+            vec!["Shopping.md", "Shopping (2).md", "a_b_ c.md"]
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = notes();
+
+        export(&notes, Format::Json, &dir.path().join("notes.json")).unwrap();
+        let text = fs::read_to_string(dir.path().join("notes.json")).unwrap();
+        let read: Vec<Note> = serde_json::from_str(&text).unwrap();
+        assert_eq!(read, notes);
+
+        export(&notes, Format::Jsonl, &dir.path().join("notes.jsonl")).unwrap();
+        let text = fs::read_to_string(dir.path().join("notes.jsonl")).unwrap();
+        let read: Vec<Note> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(read, notes);
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = notes();
+        export(&notes, Format::Markdown, dir.path()).unwrap();
+        for (note, name) in notes.iter().zip(file_names(&notes)) {
+            let text = fs::read_to_string(dir.path().join(name)).unwrap();
+            assert_eq!(&from_markdown(&text), note);
+        }
+    }
+
+    #[test]
+    fn test_tar_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = notes();
+        let path = dir.path().join("notes.tar");
+        export(&notes, Format::Tar, &path).unwrap();
+
+        let mut archive = tar::Archive::new(File::open(&path).unwrap());
+        let mut read = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut text = String::new();
+            entry.unwrap().read_to_string(&mut text).unwrap();
+            read.push(from_markdown(&text));
+        }
+        assert_eq!(read, notes);
+    }
+}
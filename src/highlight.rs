@@ -0,0 +1,102 @@
+// src/highlight.rs
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+use std::sync::LazyLock;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{FontStyle, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+// Loading the bundled definitions takes a moment, so it happens on first use
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// Fails with the list of known themes when `name` is not one of them.
+pub fn check_theme(name: &str) -> Result<(), String> {
+    if THEMES.themes.contains_key(name) {
+        return Ok(());
+    }
+    let known: Vec<&str> = THEMES.themes.keys().map(String::as_str).collect();
+    Err(format!(
+        "unknown preview theme \"{}\"; available themes: {}",
+        name,
+        known.join(", ")
+    ))
+}
+
+/// Highlights `code` line by line, or returns None when the language is unknown.
+///
+/// `language` is the first word of a fence's info string, e.g. `rust` or `sh`.
+pub fn highlight(language: &str, code: &str, theme: &str) -> Option<Vec<Vec<Span<'static>>>> {
+    let syntax = SYNTAXES
+        .find_syntax_by_token(language)
+        .or_else(|| SYNTAXES.find_syntax_by_name(language))?;
+    let theme = THEMES
+        .themes
+        .get(theme)
+        .or_else(|| THEMES.themes.get(DEFAULT_THEME))?;
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(code) {
+        let pieces = highlighter.highlight_line(line, &SYNTAXES).ok()?;
+        let spans = pieces
+            .into_iter()
+            .map(|(style, text)| (style, text.trim_end_matches(['\n', '\r'])))
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(style, text)| Span::styled(text.to_string(), convert(style)))
+            .collect();
+        lines.push(spans);
+    }
+    Some(lines)
+}
+
+// The theme's background is left to the terminal
+fn convert(style: syntect::highlighting::Style) -> Style {
+    let fg = style.foreground;
+    let mut converted = Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        converted = converted.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        converted = converted.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        converted = converted.add_modifier(Modifier::UNDERLINED);
+    }
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_language_is_coloured() {
+        let lines = highlight("rust", "fn main() {}\nlet x = 1;\n", DEFAULT_THEME).unwrap();
+        assert_eq!(lines.len(), 2);
+        let text: String = lines[0].iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "fn main() {}");
+        // keywords and names get different colours
+        assert!(lines[0].iter().any(|s| s.style != lines[0][0].style));
+    }
+
+    #[test]
+    fn test_unknown_language() {
+        assert!(highlight("no-such-language", "x", DEFAULT_THEME).is_none());
+    }
+
+    #[test]
+    fn test_check_theme() {
+        assert!(check_theme(DEFAULT_THEME).is_ok());
+        let error = check_theme("nope").unwrap_err();
+        assert!(error.contains("InspiredGitHub"));
+    }
+}
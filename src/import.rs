@@ -0,0 +1,478 @@
+// src/import.rs
+
+use crate::db::NoteClient;
+use crate::models::note::{Note, parse_tags};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Source {
+    /// A directory of `.md` files, titled by their front matter or file name
+    #[value(alias = "md")]
+    Markdown,
+    /// terminote's own JSON or JSONL export
+    Json,
+    /// An Obsidian vault, titled by file name
+    Obsidian,
+    /// The JSON file of a Simplenote export
+    Simplenote,
+    /// A Google Keep folder from Takeout, or one of its `.json` files
+    Keep,
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub created: usize,
+    /// Notes already in the database, by id or by identical title and content
+    pub skipped: usize,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Reads notes from `path` and adds those that are not in the database yet.
+pub fn import(client: &NoteClient, source: Source, path: &Path) -> Result<Summary, Box<dyn Error>> {
+    let mut summary = Summary::default();
+    let notes = match source {
+        Source::Markdown | Source::Obsidian => read_markdown_dir(path, source, &mut summary)?,
+        Source::Json => read_json(&fs::read_to_string(path)?)?,
+        Source::Simplenote => read_simplenote(&fs::read_to_string(path)?)?,
+        Source::Keep => read_keep(path, &mut summary)?,
+    };
+
+    let mut seen: HashSet<u64> = client
+        .get_all_notes()?
+        .iter()
+        .chain(client.trashed_notes()?.iter())
+        .map(|n| content_hash(&n.title, &n.content))
+        .collect();
+    for note in notes {
+        let hash = content_hash(&note.title, &note.content);
+        if client.get_note_by_id(note.id)?.is_some() || !seen.insert(hash) {
+            summary.skipped += 1;
+            continue;
+        }
+        client.add_note(&note)?;
+        summary.created += 1;
+    }
+    Ok(summary)
+}
+
+fn content_hash(title: &str, content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    title.trim().hash(&mut hasher);
+    content.trim_end().hash(&mut hasher);
+    hasher.finish()
+}
+
+// Tags are derived from the text, so labels kept outside it are appended as hashtags
+fn note_with_tags(
+    title: &str,
+    content: &str,
+    tags: &[String],
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> Note {
+    let present = parse_tags(content);
+    let missing: Vec<String> = tags
+        .iter()
+        .map(|t| {
+            t.trim()
+                .trim_start_matches('#')
+                .to_lowercase()
+                .replace(' ', "-")
+        })
+        .filter(|t| !t.is_empty() && !present.contains(t))
+        .collect();
+    let mut content = content.to_string();
+    if !missing.is_empty() {
+        if !content.trim().is_empty() {
+            content = format!("{}\n\n", content.trim_end());
+        }
+        let hashtags: Vec<String> = missing.iter().map(|t| format!("#{}", t)).collect();
+        content.push_str(&hashtags.join(" "));
+    }
+    let mut note = Note::new(title, &content);
+    note.created_at = created_at;
+    note.updated_at = updated_at;
+    note
+}
+
+fn read_markdown_dir(
+    dir: &Path,
+    source: Source,
+    summary: &mut Summary,
+) -> Result<Vec<Note>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    markdown_files(dir, &mut files)?;
+    files.sort();
+    let mut notes = Vec::new();
+    for path in files {
+        match read_markdown_file(&path, source) {
+            Ok(note) => notes.push(note),
+            Err(e) => summary.failed.push((path, e.to_string())),
+        }
+    }
+    Ok(notes)
+}
+
+// Hidden entries such as `.obsidian` and `.trash` are left out
+fn markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            markdown_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_markdown_file(path: &Path, source: Source) -> Result<Note, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let modified = DateTime::<Utc>::from(fs::metadata(path)?.modified()?);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let (front, body) = split_front_matter(&text);
+    let front: Value = match front {
+        Some(yaml) => serde_yaml::from_str(yaml)?,
+        None => Value::Null,
+    };
+    let field = |keys: &[&str]| keys.iter().find_map(|k| front.get(*k));
+    let date = |keys: &[&str]| field(keys).and_then(Value::as_str).and_then(parse_date);
+
+    // Obsidian names notes by their file, front matter rarely has a title
+    let title = match source {
+        Source::Obsidian => stem,
+        _ => field(&["title"])
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or(stem),
+    };
+    let created_at = date(&["created", "date"]).unwrap_or(modified);
+    let updated_at = date(&["updated", "modified"]).unwrap_or(modified.max(created_at));
+    let tags: Vec<String> = match field(&["tags"]) {
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(Value::String(s)) => s
+            .split([',', ' '])
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut note = note_with_tags(&title, body, &tags, created_at, updated_at);
+    if let Some(id) = field(&["id"])
+        .and_then(Value::as_str)
+        .and_then(|id| Uuid::parse_str(id).ok())
+    {
+        note.id = id;
+    }
+    Ok(note)
+}
+
+/// Splits `---` delimited YAML front matter from the body, if the text starts with it.
+fn split_front_matter(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (None, text);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, text)
+}
+
+fn parse_date(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Some(date.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+// A JSON array, or one note per line
+fn read_json(text: &str) -> Result<Vec<Note>, Box<dyn Error>> {
+    if text.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(text)?);
+    }
+    let notes = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    Ok(notes)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimplenoteExport {
+    active_notes: Vec<SimplenoteNote>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimplenoteNote {
+    content: String,
+    creation_date: DateTime<Utc>,
+    last_modified: DateTime<Utc>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+// Simplenote keeps the title as the first line of the text; trashed notes are left out
+fn read_simplenote(text: &str) -> Result<Vec<Note>, Box<dyn Error>> {
+    let export: SimplenoteExport = serde_json::from_str(text)?;
+    let notes = export
+        .active_notes
+        .into_iter()
+        .map(|n| {
+            let (first, rest) = n.content.split_once('\n').unwrap_or((&n.content, ""));
+            let title = first.trim().trim_start_matches('#').trim();
+            note_with_tags(
+                title,
+                rest.trim_start_matches(['\r', '\n']),
+                &n.tags,
+                n.creation_date,
+                n.last_modified,
+            )
+        })
+        .collect();
+    Ok(notes)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeepNote {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    text_content: String,
+    #[serde(default)]
+    list_content: Vec<KeepListItem>,
+    #[serde(default)]
+    labels: Vec<KeepLabel>,
+    #[serde(default)]
+    is_trashed: bool,
+    created_timestamp_usec: Option<i64>,
+    user_edited_timestamp_usec: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeepListItem {
+    text: String,
+    is_checked: bool,
+}
+
+#[derive(Deserialize)]
+struct KeepLabel {
+    name: String,
+}
+
+// Takeout writes one JSON file per note, next to an HTML copy
+fn read_keep(path: &Path, summary: &mut Summary) -> Result<Vec<Note>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.extension().is_some_and(|ext| ext == "json") {
+                files.push(file);
+            }
+        }
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    let mut notes = Vec::new();
+    for file in files {
+        let parsed = fs::read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str::<KeepNote>(&text).map_err(|e| e.to_string()));
+        let keep = match parsed {
+            Ok(keep) => keep,
+            Err(e) => {
+                summary.failed.push((file, e));
+                continue;
+            }
+        };
+        if keep.is_trashed {
+            continue;
+        }
+        // checklists become Markdown task lists
+        let mut content = keep.text_content;
+        for item in &keep.list_content {
+            let mark = if item.is_checked { 'x' } else { ' ' };
+            content.push_str(&format!("- [{}] {}\n", mark, item.text));
+        }
+        let usec = |t: Option<i64>| t.and_then(DateTime::from_timestamp_micros);
+        let updated_at = usec(keep.user_edited_timestamp_usec).unwrap_or_else(Utc::now);
+        let created_at = usec(keep.created_timestamp_usec).unwrap_or(updated_at);
+        let labels: Vec<String> = keep.labels.into_iter().map(|l| l.name).collect();
+        notes.push(note_with_tags(
+            &keep.title,
+            &content,
+            &labels,
+            created_at,
+            updated_at,
+        ));
+    }
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{self, Format};
+    use rusqlite::Connection;
+
+    fn client() -> NoteClient {
+        NoteClient::new(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_export_round_trip_and_dedupe() {
+        let source = client();
+        source.add_note(&Note::new("One", "first #a")).unwrap();
+        source.add_note(&Note::new("Two", "second")).unwrap();
+        let notes = source.get_all_notes().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        for (format, name, from) in [
+            (Format::Json, "notes.json", Source::Json),
+            (Format::Jsonl, "notes.jsonl", Source::Json),
+            (Format::Markdown, "md", Source::Markdown),
+        ] {
+            let path = dir.path().join(name);
+            export::export(&notes, format, &path).unwrap();
+            let target = client();
+            let summary = import(&target, from, &path).unwrap();
+            assert_eq!((summary.created, summary.skipped), (2, 0), "{:?}", format);
+            assert_eq!(target.get_all_notes().unwrap(), notes, "{:?}", format);
+
+            // a second run finds everything already there
+            let summary = import(&target, from, &path).unwrap();
+            assert_eq!((summary.created, summary.skipped), (0, 2), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_markdown_without_front_matter() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Plain note.md"), "just text").unwrap();
+        fs::write(
+            dir.path().join("other.md"),
+            "---\ntitle: From front matter\ncreated: 2024-03-01\ntags: [work, Big Plans]\n---\nbody\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join(".obsidian")).unwrap();
+        fs::write(dir.path().join(".obsidian/ignored.md"), "x").unwrap();
+        // the same text again is a duplicate
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/Plain note.md"), "just text\n").unwrap();
+
+        let target = client();
+        let summary = import(&target, Source::Markdown, dir.path()).unwrap();
+        assert_eq!((summary.created, summary.skipped), (2, 1));
+
+        let notes = target.get_all_notes().unwrap();
+        let titled = notes
+            .iter()
+            .find(|n| n.title == "From front matter")
+            .unwrap();
+        assert_eq!(titled.content, "body\n\n#work #big-plans");
+        assert_eq!(titled.tags, vec!["big-plans", "work"]);
+        assert_eq!(titled.created_at, parse_date("2024-03-01").unwrap());
+        assert!(notes.iter().any(|n| n.title == "Plain note"));
+
+        // Obsidian titles notes by file name
+        let target = client();
+        import(&target, Source::Obsidian, dir.path()).unwrap();
+        assert!(
+            target
+                .get_all_notes()
+                .unwrap()
+                .iter()
+                .any(|n| n.title == "other")
+        );
+    }
+
+    #[test]
+    fn test_simplenote_and_keep() {
+        let dir = tempfile::tempdir().unwrap();
+        let simplenote = dir.path().join("notes.json");
+        fs::write(
+            &simplenote,
+            r##"{"activeNotes": [{"id": "abc", "content": "# Groceries\n\nmilk",
+                 "creationDate": "2020-01-01T10:00:00.000Z",
+                 "lastModified": "2020-01-02T10:00:00.000Z", "tags": ["home"]}],
+                "trashedNotes": []}"##,
+        )
+        .unwrap();
+        let keep = dir.path().join("keep");
+        fs::create_dir(&keep).unwrap();
+        fs::write(
+            keep.join("list.json"),
+            r#"{"title": "Packing", "textContent": "", "isTrashed": false,
+                "listContent": [{"text": "socks", "isChecked": true},
+                                {"text": "hat", "isChecked": false}],
+                "labels": [{"name": "Travel"}],
+                "userEditedTimestampUsec": 1700000000000000}"#,
+        )
+        .unwrap();
+        fs::write(keep.join("list.html"), "<html></html>").unwrap();
+        fs::write(
+            keep.join("gone.json"),
+            r#"{"title": "Old", "textContent": "x", "isTrashed": true}"#,
+        )
+        .unwrap();
+
+        let target = client();
+        import(&target, Source::Simplenote, &simplenote).unwrap();
+        let summary = import(&target, Source::Keep, &keep).unwrap();
+        assert_eq!(summary.created, 1);
+
+        let notes = target.get_all_notes().unwrap();
+        let groceries = notes.iter().find(|n| n.title == "Groceries").unwrap();
+        assert_eq!(groceries.content, "milk\n\n#home");
+        let packing = notes.iter().find(|n| n.title == "Packing").unwrap();
+        assert_eq!(packing.content, "- [x] socks\n- [ ] hat\n\n#travel");
+        assert_eq!(packing.updated_at.timestamp(), 1_700_000_000);
+    }
+}
@@ -1,41 +1,121 @@
 // src/input.rs
 
-use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, poll, read};
+use crate::keymap::{Context, Keymap};
+use ratatui::crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind, poll, read,
+};
 use std::time::Duration;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Action {
     Up,
     Down,
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    ScrollUp,
+    ScrollDown,
     Enter,
     Esc,
     Backspace,
+    Delete,
+    Undo,
+    Redo,
     Save,
     Char(char),
+    /// A left click on the screen cell at `column`, `row`
+    Click {
+        column: u16,
+        row: u16,
+    },
+    // bound in the keymap, see `keymap::BINDINGS`
+    Quit,
+    Add,
+    Edit,
+    ExternalEdit,
+    DeleteNote,
+    Preview,
+    Search,
+    Tags,
+    History,
+    Trash,
+    Export,
+    UndoDelete,
+    SwitchFocus,
+    Sort,
+    MoveUp,
+    MoveDown,
+    Pin,
+    Favorite,
+    Favorites,
+    Notebooks,
+    MoveToNotebook,
+    NewNotebook,
+    NextLink,
+    PrevLink,
+    NextTask,
+    PrevTask,
+    ToggleTask,
+    OpenTasks,
+    Restore,
+    Purge,
+    Yes,
+    No,
 }
 
-pub fn poll_action() -> Option<Action> {
-    if poll(Duration::from_millis(100)).ok()? {
-        if let Event::Key(KeyEvent {
+/// The next action on a screen with the given key context, if an event arrives in time
+pub fn poll_action(keymap: &Keymap, context: Context) -> Option<Action> {
+    if !poll(Duration::from_millis(100)).ok()? {
+        return None;
+    }
+    match read().ok()? {
+        Event::Key(KeyEvent {
             code, modifiers, ..
-        }) = read().ok()?
-        {
-            if modifiers.contains(KeyModifiers::CONTROL) {
-                return match code {
-                    KeyCode::Char('x') => Some(Action::Save),
-                    _ => None,
-                };
-            }
-            return match code {
-                KeyCode::Up => Some(Action::Up),
-                KeyCode::Down => Some(Action::Down),
-                KeyCode::Enter => Some(Action::Enter),
-                KeyCode::Esc => Some(Action::Esc),
-                KeyCode::Backspace => Some(Action::Backspace),
-                KeyCode::Char(c) => Some(Action::Char(c)),
-                _ => None,
-            };
-        }
+        }) => keymap
+            .action(context, code, modifiers)
+            .or_else(|| key_action(code, modifiers)),
+        Event::Mouse(MouseEvent {
+            kind, column, row, ..
+        }) => match kind {
+            MouseEventKind::Down(MouseButton::Left) => Some(Action::Click { column, row }),
+            MouseEventKind::ScrollUp => Some(Action::ScrollUp),
+            MouseEventKind::ScrollDown => Some(Action::ScrollDown),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Keys that are not rebindable: navigation, editing and text
+fn key_action(code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        return match code {
+            KeyCode::Left => Some(Action::WordLeft),
+            KeyCode::Right => Some(Action::WordRight),
+            KeyCode::Up => Some(Action::ScrollUp),
+            KeyCode::Down => Some(Action::ScrollDown),
+            _ => None,
+        };
+    }
+    match code {
+        KeyCode::Up => Some(Action::Up),
+        KeyCode::Down => Some(Action::Down),
+        KeyCode::Left => Some(Action::Left),
+        KeyCode::Right => Some(Action::Right),
+        KeyCode::Home => Some(Action::Home),
+        KeyCode::End => Some(Action::End),
+        KeyCode::PageUp => Some(Action::PageUp),
+        KeyCode::PageDown => Some(Action::PageDown),
+        KeyCode::Enter => Some(Action::Enter),
+        KeyCode::Esc => Some(Action::Esc),
+        KeyCode::Backspace => Some(Action::Backspace),
+        KeyCode::Delete => Some(Action::Delete),
+        KeyCode::Char(c) => Some(Action::Char(c)),
+        _ => None,
     }
-    None
 }
@@ -0,0 +1,581 @@
+// src/keymap.rs
+
+use crate::input::Action;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The screens that have bindings of their own; each controller state maps to one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Context {
+    List,
+    Editor,
+    Trash,
+    Confirm,
+    /// The notebook tree, while no name is being typed
+    Notebooks,
+    /// The full preview and the open tasks, which both check off items
+    Preview,
+    /// Screens that only navigate or take text, like search, tags and history
+    Plain,
+}
+
+/// One key with its modifiers, as written in `Settings.toml`: "a", "E", "ctrl-x", "alt-enter"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let mut code = code;
+        if let KeyCode::Char(c) = code {
+            // the case of the letter already tells whether shift was held
+            modifiers.remove(KeyModifiers::SHIFT);
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                code = KeyCode::Char(c.to_ascii_lowercase());
+            }
+        }
+        Self { code, modifiers }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text.trim();
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, 5)
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, 4)
+            } else if lower.starts_with("shift-") {
+                (KeyModifiers::SHIFT, 6)
+            } else {
+                break;
+            };
+            modifiers |= modifier;
+            rest = &rest[len..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => KeyCode::F(
+                    name.strip_prefix('f')?
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=12).contains(n))?,
+                ),
+            },
+        };
+        Some(Self::new(code, modifiers))
+    }
+
+    // Keys the screens handle themselves, for moving around and closing
+    fn is_reserved(&self) -> bool {
+        let navigation = matches!(
+            self.code,
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right
+        );
+        let fixed = matches!(
+            self.code,
+            KeyCode::Enter
+                | KeyCode::Esc
+                | KeyCode::Home
+                | KeyCode::End
+                | KeyCode::PageUp
+                | KeyCode::PageDown
+                | KeyCode::Backspace
+                | KeyCode::Delete
+        );
+        (self.modifiers.is_empty() && (navigation || fixed))
+            || (self.modifiers == KeyModifiers::CONTROL && navigation)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Enter => write!(f, "↵"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// The keys of one action in `[keys]`: a single chord or a list of them
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+struct Binding {
+    action: Action,
+    /// The name used in `[keys]`
+    name: &'static str,
+    /// Shown in the help of the screen's title, when not empty
+    label: &'static str,
+    context: Context,
+    defaults: &'static [&'static str],
+}
+
+const fn bind(
+    action: Action,
+    name: &'static str,
+    label: &'static str,
+    context: Context,
+    defaults: &'static [&'static str],
+) -> Binding {
+    Binding {
+        action,
+        name,
+        label,
+        context,
+        defaults,
+    }
+}
+
+const BINDINGS: &[Binding] = &[
+    bind(Action::Add, "add", "add", Context::List, &["a"]),
+    bind(Action::Edit, "edit", "edit", Context::List, &["e"]),
+    bind(
+        Action::ExternalEdit,
+        "external_edit",
+        "$EDITOR",
+        Context::List,
+        &["E"],
+    ),
+    bind(Action::Preview, "preview", "preview", Context::List, &["p"]),
+    bind(
+        Action::DeleteNote,
+        "delete",
+        "delete",
+        Context::List,
+        &["d"],
+    ),
+    bind(Action::History, "history", "history", Context::List, &["h"]),
+    bind(Action::Search, "search", "search", Context::List, &["/"]),
+    bind(Action::Tags, "tags", "tags", Context::List, &["t"]),
+    bind(
+        Action::Notebooks,
+        "notebooks",
+        "notebooks",
+        Context::List,
+        &["n"],
+    ),
+    bind(
+        Action::MoveToNotebook,
+        "move_to_notebook",
+        "move",
+        Context::List,
+        &["m"],
+    ),
+    bind(Action::Trash, "trash", "trash", Context::List, &["T"]),
+    bind(Action::Export, "export", "export", Context::List, &["x"]),
+    bind(Action::Sort, "sort", "sort", Context::List, &["s"]),
+    bind(Action::Pin, "pin", "pin", Context::List, &["P"]),
+    bind(Action::Favorite, "favorite", "fav", Context::List, &["f"]),
+    bind(
+        Action::Favorites,
+        "favorites",
+        "favorites",
+        Context::List,
+        &["F"],
+    ),
+    bind(
+        Action::OpenTasks,
+        "open_tasks",
+        "tasks",
+        Context::List,
+        &["o"],
+    ),
+    bind(Action::Quit, "quit", "quit", Context::List, &["q"]),
+    // named in the preview pane's title instead
+    bind(
+        Action::SwitchFocus,
+        "switch_focus",
+        "",
+        Context::List,
+        &["tab"],
+    ),
+    // only offered right after a delete
+    bind(Action::UndoDelete, "undo_delete", "", Context::List, &["u"]),
+    // only offered in the manual sort order
+    bind(
+        Action::MoveUp,
+        "move_up",
+        "",
+        Context::List,
+        &["K", "alt-up"],
+    ),
+    bind(
+        Action::MoveDown,
+        "move_down",
+        "",
+        Context::List,
+        &["J", "alt-down"],
+    ),
+    bind(
+        Action::NextLink,
+        "next_link",
+        "next link",
+        Context::Preview,
+        &["tab"],
+    ),
+    bind(
+        Action::PrevLink,
+        "prev_link",
+        "previous link",
+        Context::Preview,
+        &["shift-tab"],
+    ),
+    bind(
+        Action::NextTask,
+        "next_task",
+        "next task",
+        Context::Preview,
+        &["n"],
+    ),
+    bind(
+        Action::PrevTask,
+        "prev_task",
+        "previous task",
+        Context::Preview,
+        &["N"],
+    ),
+    bind(
+        Action::ToggleTask,
+        "toggle_task",
+        "toggle task",
+        Context::Preview,
+        &["space"],
+    ),
+    bind(
+        Action::NewNotebook,
+        "new_notebook",
+        "new",
+        Context::Notebooks,
+        &["n"],
+    ),
+    bind(Action::Save, "save", "save", Context::Editor, &["ctrl-x"]),
+    bind(Action::Undo, "undo", "undo", Context::Editor, &["ctrl-z"]),
+    bind(Action::Redo, "redo", "redo", Context::Editor, &["ctrl-y"]),
+    bind(
+        Action::Restore,
+        "restore",
+        "restore",
+        Context::Trash,
+        &["r"],
+    ),
+    bind(
+        Action::Purge,
+        "purge",
+        "delete permanently",
+        Context::Trash,
+        &["d"],
+    ),
+    bind(Action::Yes, "yes", "yes", Context::Confirm, &["o"]),
+    bind(Action::No, "no", "no", Context::Confirm, &["x"]),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum KeymapError {
+    UnknownAction(String),
+    BadChord {
+        action: String,
+        chord: String,
+    },
+    /// Navigation and closing keys cannot be rebound
+    Reserved {
+        action: String,
+        chord: String,
+    },
+    /// Editor bindings without Ctrl or Alt would swallow typed text
+    NeedsModifier {
+        action: String,
+        chord: String,
+    },
+    Conflict {
+        chord: String,
+        first: String,
+        second: String,
+    },
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeymapError::UnknownAction(name) => write!(f, "[keys]: unknown action \"{}\"", name),
+            KeymapError::BadChord { action, chord } => {
+                write!(f, "[keys]: cannot read \"{}\" for {}", chord, action)
+            }
+            KeymapError::Reserved { action, chord } => {
+                write!(
+                    f,
+                    "[keys]: \"{}\" for {} is reserved for navigation",
+                    chord, action
+                )
+            }
+            KeymapError::NeedsModifier { action, chord } => write!(
+                f,
+                "[keys]: \"{}\" for {} needs ctrl- or alt- while typing text",
+                chord, action
+            ),
+            KeymapError::Conflict {
+                chord,
+                first,
+                second,
+            } => write!(
+                f,
+                "[keys]: \"{}\" is bound to both {} and {}",
+                chord, first, second
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+/// Translates keys into the actions of the screen they are pressed on
+pub struct Keymap {
+    actions: HashMap<(Context, KeyChord), Action>,
+    // chords of every action in binding order, for the help text
+    keys: HashMap<Action, Vec<KeyChord>>,
+}
+
+impl Keymap {
+    /// The default bindings, with the actions named in `overrides` rebound.
+    pub fn new(overrides: &HashMap<String, KeyList>) -> Result<Self, KeymapError> {
+        if let Some(name) = overrides
+            .keys()
+            .find(|name| !BINDINGS.iter().any(|b| b.name == name.as_str()))
+        {
+            return Err(KeymapError::UnknownAction(name.clone()));
+        }
+
+        let mut keymap = Keymap {
+            actions: HashMap::new(),
+            keys: HashMap::new(),
+        };
+        for binding in BINDINGS {
+            let chords: Vec<&str> = match overrides.get(binding.name) {
+                Some(KeyList::One(chord)) => vec![chord.as_str()],
+                Some(KeyList::Many(chords)) => chords.iter().map(String::as_str).collect(),
+                None => binding.defaults.to_vec(),
+            };
+            for text in chords {
+                let action = binding.name.to_string();
+                let chord = text.to_string();
+                let parsed = KeyChord::parse(text).ok_or_else(|| KeymapError::BadChord {
+                    action: action.clone(),
+                    chord: chord.clone(),
+                })?;
+                if parsed.is_reserved() {
+                    return Err(KeymapError::Reserved { action, chord });
+                }
+                if binding.context == Context::Editor
+                    && !parsed
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                {
+                    return Err(KeymapError::NeedsModifier { action, chord });
+                }
+                if let Some(other) = keymap
+                    .actions
+                    .insert((binding.context, parsed), binding.action)
+                    && other != binding.action
+                {
+                    let first = BINDINGS
+                        .iter()
+                        .find(|b| b.action == other)
+                        .map_or("", |b| b.name);
+                    return Err(KeymapError::Conflict {
+                        chord,
+                        first: first.to_string(),
+                        second: action,
+                    });
+                }
+                keymap.keys.entry(binding.action).or_default().push(parsed);
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// The bound action for a key pressed in `context`, if any
+    pub fn action(
+        &self,
+        context: Context,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> Option<Action> {
+        self.actions
+            .get(&(context, KeyChord::new(code, modifiers)))
+            .copied()
+    }
+
+    /// The first key of an action, for help texts; "?" when it is unbound
+    pub fn key(&self, action: Action) -> String {
+        self.keys
+            .get(&action)
+            .and_then(|chords| chords.first())
+            .map_or_else(|| "?".to_string(), KeyChord::to_string)
+    }
+
+    /// "key:label" pairs of every bound action of `context`, e.g. "a:add, e:edit"
+    pub fn hint(&self, context: Context) -> String {
+        BINDINGS
+            .iter()
+            .filter(|b| b.context == context && !b.label.is_empty())
+            .filter(|b| self.keys.contains_key(&b.action))
+            .map(|b| format!("{}:{}", self.key(b.action), b.label))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&HashMap::new()).expect("the default bindings are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> HashMap<String, KeyList> {
+        pairs
+            .iter()
+            .map(|(name, chord)| (name.to_string(), KeyList::One(chord.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_chords() {
+        let chord = |code, modifiers| Some(KeyChord::new(code, modifiers));
+        assert_eq!(
+            KeyChord::parse("a"),
+            chord(KeyCode::Char('a'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::parse("shift-a"),
+            chord(KeyCode::Char('A'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::parse("Ctrl-X"),
+            chord(KeyCode::Char('x'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            KeyChord::parse("-"),
+            chord(KeyCode::Char('-'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::parse("alt-f5"),
+            chord(KeyCode::F(5), KeyModifiers::ALT)
+        );
+        assert_eq!(KeyChord::parse("ctrl-nothing"), None);
+        assert_eq!(KeyChord::parse("ctrl-x").unwrap().to_string(), "Ctrl+X");
+    }
+
+    #[test]
+    fn test_translate() {
+        let keymap = Keymap::new(&overrides(&[("quit", "Q"), ("save", "ctrl-s")])).unwrap();
+        let key = |context, c: char| keymap.action(context, KeyCode::Char(c), KeyModifiers::NONE);
+        assert_eq!(key(Context::List, 'Q'), Some(Action::Quit));
+        assert_eq!(key(Context::List, 'q'), None);
+        // the same key means different things on different screens
+        assert_eq!(key(Context::List, 'x'), Some(Action::Export));
+        assert_eq!(key(Context::Confirm, 'x'), Some(Action::No));
+        assert_eq!(key(Context::Plain, 'x'), None);
+        assert_eq!(key(Context::Notebooks, 'n'), Some(Action::NewNotebook));
+        // terminals report shifted letters with and without the modifier
+        assert_eq!(
+            keymap.action(Context::List, KeyCode::Char('E'), KeyModifiers::SHIFT),
+            Some(Action::ExternalEdit)
+        );
+        assert_eq!(
+            keymap.action(Context::Editor, KeyCode::Char('s'), KeyModifiers::CONTROL),
+            Some(Action::Save)
+        );
+        assert_eq!(keymap.key(Action::Save), "Ctrl+S");
+    }
+
+    #[test]
+    fn test_invalid_bindings() {
+        assert_eq!(
+            Keymap::new(&overrides(&[("edit", "a")])).err(),
+            Some(KeymapError::Conflict {
+                chord: "a".to_string(),
+                first: "add".to_string(),
+                second: "edit".to_string()
+            })
+        );
+        assert!(matches!(
+            Keymap::new(&overrides(&[("fly", "f")])),
+            Err(KeymapError::UnknownAction(_))
+        ));
+        assert!(matches!(
+            Keymap::new(&overrides(&[("quit", "esc")])),
+            Err(KeymapError::Reserved { .. })
+        ));
+        assert!(matches!(
+            Keymap::new(&overrides(&[("save", "s")])),
+            Err(KeymapError::NeedsModifier { .. })
+        ));
+        // the same key on different screens is fine
+        assert!(Keymap::new(&overrides(&[("restore", "a")])).is_ok());
+    }
+
+    #[test]
+    fn test_hint() {
+        let keymap = Keymap::new(&overrides(&[("restore", "R")])).unwrap();
+        assert_eq!(
+            keymap.hint(Context::Trash),
+            "R:restore, d:delete permanently"
+        );
+        assert!(
+            keymap
+                .hint(Context::List)
+                .starts_with("a:add, e:edit, E:$EDITOR")
+        );
+    }
+}
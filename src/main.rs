@@ -1,5 +1,7 @@
 // src/main.rs
 
+use chrono::{Duration, Utc};
+use clap::Parser;
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::crossterm::{
@@ -12,27 +14,61 @@ use rusqlite::Connection;
 use std::{error::Error, io};
 
 mod app;
+mod cli;
 mod components;
 mod config;
 mod controller;
 mod db;
+mod editor;
+mod export;
+mod highlight;
+mod import;
 mod input;
+mod keymap;
+mod markdown;
+mod migrations;
 mod models;
+mod text_buffer;
+mod vim;
 
 fn main() {
     if let Err(e) = run() {
+        // output piped into `head` and the like was cut short on purpose
+        if e.downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+        {
+            return;
+        }
         eprintln!("Error: {}", e);
         std::process::exit(1);
     }
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
+    let cli = cli::Cli::parse();
     // Create data path if it doesn't exist
     let settings = config::Settings::new("Settings.toml")?;
+    highlight::check_theme(&settings.preview.theme)?;
+    let keymap = keymap::Keymap::new(&settings.keys)?;
     create_data_path(&settings.store.path)?;
     let connection = Connection::open(settings.db_path())?;
     let client = db::NoteClient::new(connection)?;
+    if settings.trash.retention_days > 0 {
+        let cutoff = Utc::now() - Duration::days(settings.trash.retention_days.into());
+        client.purge_trash(cutoff)?;
+    }
+    if let Some(command) = cli.command {
+        return cli::run(command, &client, &mut io::stdin(), &mut io::stdout());
+    }
+
     let mut app = app::App::new(client);
+    app.theme = settings.preview.theme;
+    app.export_dir = settings.export.dir.into();
+    app.export_format = settings.export.format;
+    app.keymap = keymap;
+    app.vim = settings.editor.vim;
+    app.layout = settings.layout;
+    app.sort = settings.list.sort;
 
     let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
@@ -59,6 +95,22 @@ impl TerminalCleanupGuard {
         execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
         Ok(Self { stdout })
     }
+
+    /// Hands the terminal back to the shell, e.g. for an external editor, until `resume`
+    pub fn suspend() -> io::Result<()> {
+        disable_raw_mode()?;
+        execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            Show
+        )
+    }
+
+    pub fn resume() -> io::Result<()> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+    }
 }
 
 impl Drop for TerminalCleanupGuard {
@@ -0,0 +1,869 @@
+// src/markdown.rs
+
+use crate::highlight;
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use std::{collections::HashSet, ops::Range};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_TASKLISTS)
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_WIKILINKS);
+
+type Row = Vec<Span<'static>>;
+// text pieces of one word or of the space before it, with their styles
+type Pieces = Vec<(String, Style)>;
+
+/// How `[[Note Title]]` links are drawn and which link or task stands out
+#[derive(Default)]
+pub struct Highlights<'a> {
+    /// Lowercased titles of the notes links can lead to; other links are flagged as
+    /// missing. `None` flags nothing.
+    pub titles: Option<&'a HashSet<String>>,
+    /// Position of the link to highlight among the links of the text
+    pub link: Option<usize>,
+    /// Position of the task to highlight among the tasks of the text
+    pub task: Option<usize>,
+}
+
+/// A `- [ ]` checklist item
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub done: bool,
+    /// The rest of the line after the checkbox
+    pub text: String,
+    // byte range of the "[ ]" in the note
+    marker: Range<usize>,
+}
+
+/// Renders CommonMark into styled lines, word wrapped to `width` columns.
+/// Fenced code is highlighted with the named syntect `theme`.
+pub fn render(
+    text: &str,
+    width: usize,
+    theme: &str,
+    highlights: &Highlights,
+) -> Vec<Line<'static>> {
+    let mut renderer = Renderer::new(width.max(1), theme, highlights);
+    for event in Parser::new_ext(text, OPTIONS) {
+        renderer.event(event);
+    }
+    renderer.flush_inline();
+    renderer.lines
+}
+
+/// The note titles that the `[[Note Title]]` links of a text lead to, in order.
+/// Links inside code do not count.
+pub fn wiki_links(text: &str) -> Vec<String> {
+    Parser::new_ext(text, OPTIONS)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link {
+                link_type: LinkType::WikiLink { .. },
+                dest_url,
+                ..
+            }) => Some(link_target(&dest_url).to_string()),
+            _ => None,
+        })
+        .filter(|target| !target.is_empty())
+        .collect()
+}
+
+/// The text with its links to the note titled `old` leading to `new` instead; the
+/// shown text of `[[old|shown]]` and the heading of `[[old#heading]]` stay
+pub fn replace_wiki_links(text: &str, old: &str, new: &str) -> String {
+    let mut ranges = Vec::new();
+    for (event, range) in Parser::new_ext(text, OPTIONS).into_offset_iter() {
+        if let Event::Start(Tag::Link {
+            link_type: LinkType::WikiLink { .. },
+            dest_url,
+            ..
+        }) = event
+            && link_target(&dest_url).eq_ignore_ascii_case(old.trim())
+        {
+            ranges.push(range);
+        }
+    }
+    let mut text = text.to_string();
+    for range in ranges.into_iter().rev() {
+        let inner = &text[range.start + 2..range.end - 2];
+        let rest = inner.find(['|', '#']).map_or("", |i| &inner[i..]);
+        let link = format!("[[{}{}]]", new, rest);
+        text.replace_range(range, &link);
+    }
+    text
+}
+
+/// The checklist items of a text, in order. Items inside code do not count.
+pub fn tasks(text: &str) -> Vec<Task> {
+    Parser::new_ext(text, OPTIONS)
+        .into_offset_iter()
+        .filter_map(|(event, marker)| match event {
+            Event::TaskListMarker(done) => {
+                let rest = &text[marker.end..];
+                let line = rest.lines().next().unwrap_or_default();
+                Some(Task {
+                    done,
+                    text: line.trim().to_string(),
+                    marker,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// How many of the tasks of a text are done, and how many there are
+pub fn task_counts(text: &str) -> (usize, usize) {
+    let tasks = tasks(text);
+    (tasks.iter().filter(|t| t.done).count(), tasks.len())
+}
+
+/// The text with its task at `index` checked, or unchecked when it was done
+pub fn toggle_task(text: &str, index: usize) -> Option<String> {
+    let task = tasks(text).into_iter().nth(index)?;
+    let mut text = text.to_string();
+    text.replace_range(task.marker, if task.done { "[ ]" } else { "[x]" });
+    Some(text)
+}
+
+// The note title of a link destination, without a `#heading`
+fn link_target(dest: &str) -> &str {
+    dest.split('#').next().unwrap_or_default().trim()
+}
+
+// Highlighted lines of a fenced code block, one row per source line
+fn code_lines(info: &str, code: &str, theme: &str) -> Vec<Row> {
+    // info strings may carry attributes after the language, e.g. "rust,ignore"
+    let language = info
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .next()
+        .unwrap_or("");
+    if !language.is_empty()
+        && let Some(lines) = highlight::highlight(language, code, theme)
+    {
+        return lines;
+    }
+    // unknown languages keep a single code colour
+    let style = Style::default().fg(Color::Yellow);
+    code.lines()
+        .map(|line| vec![Span::styled(line.to_string(), style)])
+        .collect()
+}
+
+// Blocks that prefix every line they contain
+enum Container {
+    Quote,
+    // the bullet goes on the item's first line, later lines are indented by its width
+    Item { bullet: Span<'static>, first: bool },
+}
+
+struct Table {
+    rows: Vec<Vec<Row>>,
+    header: bool,
+}
+
+struct Renderer<'a> {
+    width: usize,
+    theme: &'a str,
+    highlights: &'a Highlights<'a>,
+    // wiki links and tasks seen so far, and whether each open link is a missing one
+    wiki_count: usize,
+    task_count: usize,
+    open_links: Vec<bool>,
+    lines: Vec<Line<'static>>,
+    styles: Vec<Style>,
+    containers: Vec<Container>,
+    // next number of each open list, None when unordered
+    lists: Vec<Option<u64>>,
+    // inline text of the current block, split at hard breaks
+    inline: Vec<Row>,
+    code: Option<(String, String)>,
+    table: Option<Table>,
+    needs_gap: bool,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(width: usize, theme: &'a str, highlights: &'a Highlights<'a>) -> Self {
+        Self {
+            width,
+            theme,
+            highlights,
+            wiki_count: 0,
+            task_count: 0,
+            open_links: Vec::new(),
+            lines: Vec::new(),
+            styles: Vec::new(),
+            containers: Vec::new(),
+            lists: Vec::new(),
+            inline: Vec::new(),
+            code: None,
+            table: None,
+            needs_gap: false,
+        }
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, f: impl FnOnce(Style) -> Style) {
+        self.styles.push(f(self.style()));
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if let Some((_, code)) = &mut self.code {
+                    code.push_str(&text);
+                } else {
+                    self.text(&text, self.style());
+                }
+            }
+            Event::Code(text) => self.text(&text, self.style().fg(Color::Yellow)),
+            Event::InlineMath(text) | Event::DisplayMath(text) => self.text(&text, self.style()),
+            Event::Html(text) | Event::InlineHtml(text) => {
+                self.text(&text, self.style().fg(Color::DarkGray))
+            }
+            Event::FootnoteReference(name) => self.text(&format!("[^{}]", name), self.style()),
+            Event::SoftBreak => self.text(" ", self.style()),
+            Event::HardBreak => self.inline.push(Vec::new()),
+            Event::Rule => {
+                self.flush_inline();
+                self.gap();
+                let rule = "─".repeat(self.available());
+                self.emit(vec![Span::styled(
+                    rule,
+                    Style::default().fg(Color::DarkGray),
+                )]);
+                self.needs_gap = true;
+            }
+            Event::TaskListMarker(done) => {
+                let selected = self.highlights.task == Some(self.task_count);
+                self.task_count += 1;
+                if let Some(Container::Item { bullet, .. }) = self.containers.last_mut() {
+                    let (text, color) = if done {
+                        ("[x] ", Color::Green)
+                    } else {
+                        ("[ ] ", Color::Cyan)
+                    };
+                    let mut style = Style::default().fg(color);
+                    if selected {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    }
+                    *bullet = Span::styled(text, style);
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.flush_inline(),
+            Tag::Heading { level, .. } => {
+                self.flush_inline();
+                self.push_style(|s| match level {
+                    HeadingLevel::H1 => s.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    HeadingLevel::H2 => s.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                    _ => s.add_modifier(Modifier::BOLD),
+                });
+            }
+            Tag::BlockQuote(_) => {
+                self.flush_inline();
+                self.gap();
+                self.containers.push(Container::Quote);
+                self.push_style(|s| s.fg(Color::Gray));
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush_inline();
+                let info = match kind {
+                    CodeBlockKind::Fenced(info) => info.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((info, String::new()));
+            }
+            Tag::List(start) => {
+                self.flush_inline();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_inline();
+                self.gap();
+                let depth = self.lists.len();
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => match depth {
+                        0 | 1 => "• ",
+                        2 => "◦ ",
+                        _ => "▪ ",
+                    }
+                    .to_string(),
+                };
+                self.containers.push(Container::Item {
+                    bullet: Span::styled(bullet, Style::default().fg(Color::Cyan)),
+                    first: true,
+                });
+            }
+            Tag::Table(_) => {
+                self.flush_inline();
+                self.table = Some(Table {
+                    rows: Vec::new(),
+                    header: false,
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.push_style(|s| s.add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(|s| s.add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self.push_style(|s| s.add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link {
+                link_type: LinkType::WikiLink { .. },
+                dest_url,
+                ..
+            } => {
+                let target = link_target(&dest_url).to_ascii_lowercase();
+                let missing = self.highlights.titles.is_some_and(|t| !t.contains(&target));
+                let selected = self.highlights.link == Some(self.wiki_count);
+                self.wiki_count += 1;
+                self.open_links.push(missing);
+                self.push_style(|s| {
+                    let s = if missing {
+                        s.fg(Color::Red)
+                    } else {
+                        s.fg(Color::Magenta).add_modifier(Modifier::UNDERLINED)
+                    };
+                    if selected {
+                        s.add_modifier(Modifier::REVERSED)
+                    } else {
+                        s
+                    }
+                });
+            }
+            Tag::Link { .. } => {
+                self.open_links.push(false);
+                self.push_style(|s| s.fg(Color::Blue).add_modifier(Modifier::UNDERLINED))
+            }
+            Tag::Image { .. } => {
+                self.push_style(|s| s.fg(Color::Blue).add_modifier(Modifier::UNDERLINED))
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                self.flush_inline();
+                self.needs_gap = true;
+            }
+            TagEnd::Heading(_) => {
+                self.flush_inline();
+                self.styles.pop();
+                self.needs_gap = true;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_inline();
+                self.containers.pop();
+                self.styles.pop();
+                self.needs_gap = true;
+            }
+            TagEnd::CodeBlock => {
+                if let Some((info, code)) = self.code.take() {
+                    self.gap();
+                    let width = self.available();
+                    for row in code_lines(&info, &code, self.theme) {
+                        for wrapped in hard_wrap(row, width) {
+                            self.emit(wrapped);
+                        }
+                    }
+                    self.needs_gap = true;
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush_inline();
+                self.lists.pop();
+                // nested lists run on into their parent item
+                if self.lists.is_empty() {
+                    self.needs_gap = true;
+                }
+            }
+            TagEnd::Item => {
+                self.flush_inline();
+                // an empty item still shows its bullet
+                if let Some(Container::Item { first: true, .. }) = self.containers.last() {
+                    self.emit(Vec::new());
+                }
+                self.containers.pop();
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header = true;
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.gap();
+                    self.render_table(table);
+                    self.needs_gap = true;
+                }
+            }
+            TagEnd::Link => {
+                self.styles.pop();
+                if self.open_links.pop() == Some(true) {
+                    self.text(" (missing)", self.style().fg(Color::DarkGray));
+                }
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Image => {
+                self.styles.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str, style: Style) {
+        if let Some(cell) = self
+            .table
+            .as_mut()
+            .and_then(|t| t.rows.last_mut())
+            .and_then(|r| r.last_mut())
+        {
+            cell.push(Span::styled(text.to_string(), style));
+            return;
+        }
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 || self.inline.is_empty() {
+                self.inline.push(Vec::new());
+            }
+            if !part.is_empty()
+                && let Some(row) = self.inline.last_mut()
+            {
+                row.push(Span::styled(part.to_string(), style));
+            }
+        }
+    }
+
+    // Width left for text once the container prefixes are drawn
+    fn available(&self) -> usize {
+        let prefix: usize = self
+            .containers
+            .iter()
+            .map(|c| match c {
+                Container::Quote => 2,
+                Container::Item { bullet, .. } => bullet.width(),
+            })
+            .sum();
+        self.width.saturating_sub(prefix).max(1)
+    }
+
+    // Appends a row behind the prefixes of its containers
+    fn emit(&mut self, row: Row) {
+        let mut spans = Vec::new();
+        for container in &mut self.containers {
+            match container {
+                Container::Quote => {
+                    spans.push(Span::styled("│ ", Style::default().fg(Color::DarkGray)))
+                }
+                Container::Item { bullet, first } => {
+                    if *first {
+                        spans.push(bullet.clone());
+                        *first = false;
+                    } else {
+                        spans.push(Span::raw(" ".repeat(bullet.width())));
+                    }
+                }
+            }
+        }
+        spans.extend(row);
+        self.lines.push(Line::from(spans));
+        self.needs_gap = false;
+    }
+
+    // A blank line between two blocks, keeping the quote bars
+    fn gap(&mut self) {
+        if self.needs_gap && !self.lines.is_empty() {
+            let spans: Row = self
+                .containers
+                .iter()
+                .filter(|c| matches!(c, Container::Quote))
+                .map(|_| Span::styled("│ ", Style::default().fg(Color::DarkGray)))
+                .collect();
+            self.lines.push(Line::from(spans));
+        }
+        self.needs_gap = false;
+    }
+
+    fn flush_inline(&mut self) {
+        let inline = std::mem::take(&mut self.inline);
+        if inline.iter().all(|row| row.is_empty()) {
+            return;
+        }
+        self.gap();
+        let width = self.available();
+        for row in inline {
+            for wrapped in wrap(row, width) {
+                self.emit(wrapped);
+            }
+        }
+    }
+
+    fn render_table(&mut self, table: Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in &table.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.iter().map(Span::width).sum());
+            }
+        }
+        let separator = Style::default().fg(Color::DarkGray);
+        let width = self.available();
+        for (r, row) in table.rows.into_iter().enumerate() {
+            let header = table.header && r == 0;
+            let mut spans = Vec::new();
+            for (i, cell) in row.into_iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::styled(" │ ", separator));
+                }
+                let used: usize = cell.iter().map(Span::width).sum();
+                for span in cell {
+                    spans.push(if header {
+                        span.patch_style(Style::default().add_modifier(Modifier::BOLD))
+                    } else {
+                        span
+                    });
+                }
+                spans.push(Span::raw(" ".repeat(widths[i] - used)));
+            }
+            for wrapped in hard_wrap(spans, width) {
+                self.emit(wrapped);
+            }
+            if header {
+                let rule: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+                let rule = Span::styled(rule.join("─┼─"), separator);
+                for wrapped in hard_wrap(vec![rule], width) {
+                    self.emit(wrapped);
+                }
+            }
+        }
+    }
+}
+
+// Adds text to a row, merging it into the last span when the style matches
+fn push_text(row: &mut Row, text: &str, style: Style) {
+    match row.last_mut() {
+        Some(last) if last.style == style => last.content.to_mut().push_str(text),
+        _ => row.push(Span::styled(text.to_string(), style)),
+    }
+}
+
+fn row_width(row: &Row) -> usize {
+    row.iter().map(Span::width).sum()
+}
+
+/// Greedy word wrap; words longer than a whole row are broken between characters.
+fn wrap(row: Row, width: usize) -> Vec<Row> {
+    // words may span several styles, e.g. "**bo**ld"
+    let mut words: Vec<(Pieces, Pieces)> = Vec::new();
+    let mut space = Vec::new();
+    let mut word: Pieces = Vec::new();
+    for span in &row {
+        let mut chunk = String::new();
+        let mut in_space = None;
+        for c in span.content.chars() {
+            let is_space = c.is_whitespace();
+            if in_space.is_some_and(|s| s != is_space) {
+                let text = std::mem::take(&mut chunk);
+                if is_space {
+                    word.push((text, span.style));
+                } else {
+                    if !word.is_empty() {
+                        words.push((std::mem::take(&mut space), std::mem::take(&mut word)));
+                    }
+                    space.push((text, span.style));
+                }
+            }
+            in_space = Some(is_space);
+            chunk.push(c);
+        }
+        match in_space {
+            Some(true) => {
+                if !word.is_empty() {
+                    words.push((std::mem::take(&mut space), std::mem::take(&mut word)));
+                }
+                space.push((chunk, span.style));
+            }
+            Some(false) => word.push((chunk, span.style)),
+            None => {}
+        }
+    }
+    if !word.is_empty() {
+        words.push((space, word));
+    }
+
+    let mut rows = vec![Vec::new()];
+    for (space, word) in words {
+        let word_width: usize = word.iter().map(|(t, _)| t.width()).sum();
+        let space_width: usize = space.iter().map(|(t, _)| t.width()).sum();
+        let current = rows.last_mut().unwrap();
+        let used = row_width(current);
+        if used > 0 && used + space_width + word_width <= width {
+            for (text, style) in &space {
+                push_text(current, text, *style);
+            }
+        } else if used > 0 {
+            rows.push(Vec::new());
+        }
+        if word_width <= width {
+            let current = rows.last_mut().unwrap();
+            for (text, style) in &word {
+                push_text(current, text, *style);
+            }
+        } else {
+            let spans = word
+                .into_iter()
+                .map(|(text, style)| Span::styled(text, style))
+                .collect();
+            let mut broken = hard_wrap(spans, width);
+            let current = rows.last_mut().unwrap();
+            current.append(&mut broken.remove(0));
+            rows.extend(broken);
+        }
+    }
+    rows
+}
+
+/// Breaks a row between characters so that no piece is wider than `width`.
+fn hard_wrap(row: Row, width: usize) -> Vec<Row> {
+    let mut rows = vec![Vec::new()];
+    let mut used = 0;
+    for span in row {
+        for c in span.content.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w > width && used > 0 {
+                rows.push(Vec::new());
+                used = 0;
+            }
+            push_text(
+                rows.last_mut().unwrap(),
+                c.encode_utf8(&mut [0; 4]),
+                span.style,
+            );
+            used += w;
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_blocks() {
+        let text =
+            "# Title\n\nSome *emphasis* and `code`.\n\n> quoted\n\n---\n\n```\nlet x = 1;\n```";
+        assert_eq!(
+            plain(&render(
+                text,
+                40,
+                highlight::DEFAULT_THEME,
+                &Highlights::default()
+            )),
+            vec![
+                "Title",
+                "",
+                "Some emphasis and code.",
+                "",
+                "│ quoted",
+                "",
+                &"─".repeat(40),
+                "",
+                "let x = 1;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lists_and_tasks() {
+        let text = "- one\n  - nested\n- [ ] todo\n- [x] done\n\n3. three\n4. four";
+        assert_eq!(
+            plain(&render(
+                text,
+                40,
+                highlight::DEFAULT_THEME,
+                &Highlights::default()
+            )),
+            vec![
+                "• one",
+                "  ◦ nested",
+                "[ ] todo",
+                "[x] done",
+                "",
+                "3. three",
+                "4. four",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tasks() {
+        let text = "- [ ] milk\n- [x] eggs  \n  - [ ] free range\n\n```\n- [ ] code\n```";
+        let tasks = tasks(text);
+        let items: Vec<(bool, &str)> = tasks.iter().map(|t| (t.done, t.text.as_str())).collect();
+        assert_eq!(
+            items,
+            [(false, "milk"), (true, "eggs"), (false, "free range")]
+        );
+        assert_eq!(task_counts(text), (1, 3));
+
+        assert_eq!(
+            toggle_task(text, 1).unwrap(),
+            "- [ ] milk\n- [ ] eggs  \n  - [ ] free range\n\n```\n- [ ] code\n```"
+        );
+        assert_eq!(task_counts(&toggle_task(text, 2).unwrap()), (2, 3));
+        assert!(toggle_task(text, 3).is_none());
+
+        let highlights = Highlights {
+            task: Some(1),
+            ..Highlights::default()
+        };
+        let lines = render(text, 40, highlight::DEFAULT_THEME, &highlights);
+        assert!(
+            !lines[0].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::REVERSED)
+        );
+        assert!(
+            lines[1].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::REVERSED)
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let text = "| a | long |\n|---|---|\n| xyz | b |";
+        assert_eq!(
+            plain(&render(
+                text,
+                40,
+                highlight::DEFAULT_THEME,
+                &Highlights::default()
+            )),
+            vec!["a   │ long", "────┼─────", "xyz │ b   "]
+        );
+    }
+
+    #[test]
+    fn test_word_wrap_keeps_prefixes() {
+        let text = "- alpha beta gamma delta\n\n> unbreakablewordhere";
+        assert_eq!(
+            plain(&render(
+                text,
+                12,
+                highlight::DEFAULT_THEME,
+                &Highlights::default()
+            )),
+            vec![
+                "• alpha beta",
+                "  gamma",
+                "  delta",
+                "",
+                "│ unbreakabl",
+                "│ ewordhere",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_languages() {
+        let yellow = Style::default().fg(Color::Yellow);
+        let lines = render(
+            "```rust,ignore\nfn f() {}\n```",
+            40,
+            highlight::DEFAULT_THEME,
+            &Highlights::default(),
+        );
+        assert_eq!(plain(&lines), vec!["fn f() {}"]);
+        assert!(lines[0].spans.iter().all(|s| s.style != yellow));
+
+        let lines = render(
+            "```klingon\nqapla'\n```",
+            40,
+            highlight::DEFAULT_THEME,
+            &Highlights::default(),
+        );
+        assert_eq!(lines[0].spans[0].style, yellow);
+    }
+
+    #[test]
+    fn test_wrap_styles() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let rows = wrap(
+            vec![
+                Span::raw("aa "),
+                Span::styled("bo", bold),
+                Span::raw("ld cc"),
+            ],
+            6,
+        );
+        // "bold" stays one word across the style change
+        let text: Vec<String> = rows
+            .iter()
+            .map(|r| r.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(text, vec!["aa", "bold", "cc"]);
+        assert_eq!(rows[1][0].style, bold);
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let text = "See [[Plans]], [[groceries|the list]] and [[Plans#Monday]].\n\n`[[code]]`";
+        assert_eq!(wiki_links(text), ["Plans", "groceries", "Plans"]);
+
+        let titles = HashSet::from(["plans".to_string()]);
+        let highlights = Highlights {
+            titles: Some(&titles),
+            link: Some(2),
+            task: None,
+        };
+        let lines = render(text, 80, highlight::DEFAULT_THEME, &highlights);
+        assert_eq!(
+            plain(&lines)[0],
+            "See Plans, the list (missing) and Plans#Monday."
+        );
+        let styles: Vec<Style> = lines[0].spans.iter().map(|s| s.style).collect();
+        assert_eq!(styles[1].fg, Some(Color::Magenta));
+        assert_eq!(styles[3].fg, Some(Color::Red));
+        assert!(styles[6].add_modifier.contains(Modifier::REVERSED));
+
+        assert_eq!(
+            replace_wiki_links(text, "plans", "Weekly plans"),
+            "See [[Weekly plans]], [[groceries|the list]] and [[Weekly plans#Monday]].\n\n`[[code]]`"
+        );
+    }
+}
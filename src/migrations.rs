@@ -0,0 +1,529 @@
+// src/migrations.rs
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag};
+use rusqlite::{Connection, Transaction, params};
+use std::fmt;
+use uuid::Uuid;
+
+/// One schema step; the step at index `i` upgrades a database from version `i` to `i + 1`.
+/// Steps are frozen once released: change the schema by appending a new one.
+pub struct Migration {
+    description: &'static str,
+    up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "create notes",
+        up: create_notes,
+    },
+    Migration {
+        description: "full-text search index",
+        up: add_search_index,
+    },
+    Migration {
+        description: "tags",
+        up: add_tags,
+    },
+    Migration {
+        description: "note revisions",
+        up: add_revisions,
+    },
+    Migration {
+        description: "trash",
+        up: add_trash,
+    },
+    Migration {
+        description: "list pagination index",
+        up: add_list_index,
+    },
+    Migration {
+        description: "sort orders",
+        up: add_sort_orders,
+    },
+    Migration {
+        description: "pinned and favorite notes",
+        up: add_note_flags,
+    },
+    Migration {
+        description: "notebooks",
+        up: add_notebooks,
+    },
+    Migration {
+        description: "links between notes",
+        up: add_note_links,
+    },
+];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Sqlite(rusqlite::Error),
+    Failed {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+    /// The database was written by a newer terminote
+    TooNew {
+        found: u32,
+        supported: u32,
+    },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Sqlite(e) => write!(f, "{}", e),
+            MigrationError::Failed {
+                version,
+                description,
+                source,
+            } => write!(
+                f,
+                "migrating the database to version {} ({}) failed: {}",
+                version, description, source
+            ),
+            MigrationError::TooNew { found, supported } => write!(
+                f,
+                "database schema version {} is newer than the supported version {}; \
+                 please upgrade terminote",
+                found, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(e: rusqlite::Error) -> Self {
+        MigrationError::Sqlite(e)
+    }
+}
+
+pub fn user_version(connection: &Connection) -> rusqlite::Result<u32> {
+    connection.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Brings the database up to the latest version, one transaction per step.
+pub fn migrate(connection: &mut Connection) -> Result<(), MigrationError> {
+    migrate_with(connection, MIGRATIONS)
+}
+
+fn migrate_with(
+    connection: &mut Connection,
+    migrations: &[Migration],
+) -> Result<(), MigrationError> {
+    let supported = migrations.len() as u32;
+    let found = user_version(connection)?;
+    if found > supported {
+        return Err(MigrationError::TooNew { found, supported });
+    }
+
+    for (index, migration) in migrations.iter().enumerate().skip(found as usize) {
+        let version = index as u32 + 1;
+        let step = |tx: &Transaction| -> rusqlite::Result<()> {
+            (migration.up)(tx)?;
+            // user_version is part of the transaction, so a failed step leaves it untouched
+            tx.pragma_update(None, "user_version", version)
+        };
+        let tx = connection.transaction()?;
+        step(&tx).map_err(|source| MigrationError::Failed {
+            version,
+            description: migration.description,
+            source,
+        })?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+// Databases from before migrations existed already have this table at version 0
+fn create_notes(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS notes (
+             id TEXT PRIMARY KEY,
+             title TEXT NOT NULL,
+             content TEXT NOT NULL,
+             created_at TEXT NOT NULL,
+             updated_at TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS idx_notes_created_at ON notes(created_at);",
+    )
+}
+
+fn add_search_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+             title,
+             content,
+             content = 'notes',
+             content_rowid = 'rowid'
+         );
+         CREATE TRIGGER IF NOT EXISTS notes_fts_insert AFTER INSERT ON notes BEGIN
+             INSERT INTO notes_fts(rowid, title, content)
+             VALUES (new.rowid, new.title, new.content);
+         END;
+         CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON notes BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content)
+             VALUES ('delete', old.rowid, old.title, old.content);
+         END;
+         CREATE TRIGGER IF NOT EXISTS notes_fts_update AFTER UPDATE ON notes BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content)
+             VALUES ('delete', old.rowid, old.title, old.content);
+             INSERT INTO notes_fts(rowid, title, content)
+             VALUES (new.rowid, new.title, new.content);
+         END;
+         INSERT INTO notes_fts(notes_fts) VALUES ('rebuild');",
+    )
+}
+
+fn add_tags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tags (
+             id INTEGER PRIMARY KEY,
+             name TEXT NOT NULL UNIQUE
+         );
+         CREATE TABLE IF NOT EXISTS note_tags (
+             note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+             tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+             PRIMARY KEY (note_id, tag_id)
+         );
+         CREATE INDEX IF NOT EXISTS idx_note_tags_tag_id ON note_tags(tag_id);",
+    )?;
+
+    // Hashtags written before tags existed
+    let notes: Vec<(Uuid, String)> = tx
+        .prepare("SELECT id, content FROM notes")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    for (id, content) in notes {
+        for tag in hashtags(&content) {
+            tx.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                params![tag],
+            )?;
+            tx.execute(
+                "INSERT OR IGNORE INTO note_tags (note_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                params![id, tag],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Every edit of a title or content keeps the previous text as a numbered revision
+fn add_revisions(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE note_revisions (
+             note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+             revision INTEGER NOT NULL,
+             title TEXT NOT NULL,
+             content TEXT NOT NULL,
+             updated_at TEXT NOT NULL,
+             PRIMARY KEY (note_id, revision)
+         );
+         CREATE TRIGGER notes_keep_revision BEFORE UPDATE OF title, content ON notes
+         WHEN old.title IS NOT new.title OR old.content IS NOT new.content
+         BEGIN
+             INSERT INTO note_revisions (note_id, revision, title, content, updated_at)
+             VALUES (
+                 old.id,
+                 (SELECT COALESCE(MAX(revision), 0) + 1
+                  FROM note_revisions WHERE note_id = old.id),
+                 old.title,
+                 old.content,
+                 old.updated_at
+             );
+         END;",
+    )
+}
+
+fn add_trash(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE notes ADD COLUMN deleted_at TEXT;
+         CREATE INDEX idx_notes_deleted_at ON notes(deleted_at);",
+    )
+}
+
+// Serves the keyset pages of the note list, newest first
+fn add_list_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE INDEX idx_notes_live_created ON notes(created_at DESC, id DESC)
+         WHERE deleted_at IS NULL;",
+    )
+}
+
+// A hand-arranged position, starting out in creation order, and indexes for every order
+fn add_sort_orders(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE notes ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+         CREATE INDEX idx_notes_live_updated ON notes(updated_at DESC, id DESC)
+         WHERE deleted_at IS NULL;
+         CREATE INDEX idx_notes_live_title ON notes(title COLLATE NOCASE, id)
+         WHERE deleted_at IS NULL;
+         CREATE INDEX idx_notes_position ON notes(position, id);",
+    )?;
+    let ids: Vec<Uuid> = tx
+        .prepare("SELECT id FROM notes ORDER BY created_at DESC, id DESC")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for (position, id) in ids.iter().enumerate() {
+        tx.execute(
+            "UPDATE notes SET position = ?1 WHERE id = ?2",
+            params![position as i64, id],
+        )?;
+    }
+    Ok(())
+}
+
+// Pinned notes lead every sort order, so the list indexes lead with the flag too
+fn add_note_flags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE notes ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+         ALTER TABLE notes ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;
+         DROP INDEX idx_notes_live_created;
+         DROP INDEX idx_notes_live_updated;
+         DROP INDEX idx_notes_live_title;
+         CREATE INDEX idx_notes_live_created ON notes(pinned DESC, created_at DESC, id DESC)
+         WHERE deleted_at IS NULL;
+         CREATE INDEX idx_notes_live_updated ON notes(pinned DESC, updated_at DESC, id DESC)
+         WHERE deleted_at IS NULL;
+         CREATE INDEX idx_notes_live_title ON notes(pinned DESC, title COLLATE NOCASE, id)
+         WHERE deleted_at IS NULL;
+         CREATE INDEX idx_notes_favorite ON notes(id) WHERE favorite AND deleted_at IS NULL;",
+    )
+}
+
+// Nestable notebooks; deleting one takes the notebooks below it along and leaves its
+// notes without a notebook
+fn add_notebooks(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE notebooks (
+             id BLOB PRIMARY KEY,
+             name TEXT NOT NULL,
+             parent_id BLOB REFERENCES notebooks(id) ON DELETE CASCADE
+         );
+         CREATE UNIQUE INDEX idx_notebooks_name
+         ON notebooks(COALESCE(parent_id, x''), name COLLATE NOCASE);
+         ALTER TABLE notes ADD COLUMN notebook_id BLOB
+             REFERENCES notebooks(id) ON DELETE SET NULL;
+         CREATE INDEX idx_notes_notebook ON notes(notebook_id);",
+    )
+}
+
+// The titles each note links to with [[Title]], read from the existing notes
+fn add_note_links(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE note_links (
+             source_id BLOB NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+             target TEXT NOT NULL COLLATE NOCASE,
+             PRIMARY KEY (source_id, target)
+         );
+         CREATE INDEX idx_note_links_target ON note_links(target);",
+    )?;
+    let notes: Vec<(Uuid, String)> = tx
+        .prepare("SELECT id, content FROM notes")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    for (id, content) in notes {
+        for target in link_targets(&content) {
+            tx.execute(
+                "INSERT OR IGNORE INTO note_links (source_id, target) VALUES (?1, ?2)",
+                params![id, target],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// The `#tag` rule of the tags step. The app's `parse_tags` may change later, so the step
+// keeps its own copy.
+fn hashtags(content: &str) -> Vec<String> {
+    let is_tag_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '/');
+    let mut tags = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let mut prev: Option<char> = None;
+        for (i, c) in line.char_indices() {
+            let starts_tag = c == '#'
+                && !prev.is_some_and(|p| p.is_alphanumeric() || matches!(p, '#' | '&' | '/'));
+            prev = Some(c);
+            if !starts_tag {
+                continue;
+            }
+            let rest = &line[i + 1..];
+            let end = rest.find(|c: char| !is_tag_char(c)).unwrap_or(rest.len());
+            let tag = rest[..end].trim_end_matches(['-', '/']);
+            if tag.chars().any(char::is_alphabetic) {
+                tags.push(tag.to_lowercase());
+            }
+        }
+    }
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+// The `[[Title]]` rule of the links step, copied from `markdown::wiki_links` like
+// `hashtags` above
+fn link_targets(content: &str) -> Vec<String> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_WIKILINKS;
+    Parser::new_ext(content, options)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link {
+                link_type: LinkType::WikiLink { .. },
+                dest_url,
+                ..
+            }) => Some(
+                dest_url
+                    .split('#')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            ),
+            _ => None,
+        })
+        .filter(|target| !target.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NoteClient;
+    use crate::models::note::Note;
+
+    const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+    // A database as the given release left it, holding one tagged note
+    fn fixture(version: u32) -> (Connection, Note) {
+        let mut connection = Connection::open_in_memory().unwrap();
+        if version == 0 {
+            // the unversioned layout shipped before migrations
+            connection
+                .execute_batch(
+                    "CREATE TABLE notes (
+                         id TEXT PRIMARY KEY,
+                         title TEXT NOT NULL,
+                         content TEXT NOT NULL,
+                         created_at TEXT NOT NULL,
+                         updated_at TEXT NOT NULL
+                     );
+                     CREATE INDEX idx_notes_created_at ON notes(created_at);",
+                )
+                .unwrap();
+        } else {
+            migrate_with(&mut connection, &MIGRATIONS[..version as usize]).unwrap();
+        }
+        assert_eq!(user_version(&connection).unwrap(), version);
+
+        let note = Note::new("Fixture", "written by an old release #legacy");
+        connection
+            .execute(
+                "INSERT INTO notes (id, title, content, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    note.id,
+                    note.title,
+                    note.content,
+                    note.created_at,
+                    note.updated_at
+                ],
+            )
+            .unwrap();
+        (connection, note)
+    }
+
+    #[test]
+    fn test_upgrades_every_historical_version() {
+        for version in 0..=SCHEMA_VERSION {
+            let (connection, note) = fixture(version);
+            let client = NoteClient::new(connection)
+                .unwrap_or_else(|e| panic!("upgrading from version {}: {}", version, e));
+
+            let loaded = client.get_note_by_id(note.id).unwrap().unwrap();
+            assert_eq!(loaded.content, note.content, "from version {}", version);
+            assert_eq!(
+                client.search("old release", 10).unwrap().len(),
+                1,
+                "from version {}",
+                version
+            );
+            if version < 3 {
+                // tags were introduced in version 3 and backfilled from content
+                assert_eq!(loaded.tags, vec!["legacy"], "from version {}", version);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fresh_database_is_current() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrate(&mut connection).unwrap();
+        assert_eq!(user_version(&connection).unwrap(), SCHEMA_VERSION);
+        // running again is a no-op
+        migrate(&mut connection).unwrap();
+        assert_eq!(user_version(&connection).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_refuses_newer_database() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        match NoteClient::new(connection) {
+            Err(MigrationError::TooNew { found, supported }) => {
+                assert_eq!(found, SCHEMA_VERSION + 1);
+                assert_eq!(supported, SCHEMA_VERSION);
+            }
+            other => panic!("expected TooNew, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_failed_step_is_rolled_back() {
+        fn broken(tx: &Transaction) -> rusqlite::Result<()> {
+            tx.execute_batch("CREATE TABLE half_done (id INTEGER); SELECT * FROM missing;")
+        }
+        let steps = [
+            Migration {
+                description: "create notes",
+                up: create_notes,
+            },
+            Migration {
+                description: "broken",
+                up: broken,
+            },
+        ];
+
+        let mut connection = Connection::open_in_memory().unwrap();
+        assert!(matches!(
+            migrate_with(&mut connection, &steps),
+            Err(MigrationError::Failed { version: 2, .. })
+        ));
+        assert_eq!(user_version(&connection).unwrap(), 1);
+        let half_done: bool = connection
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'half_done')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!half_done);
+    }
+}
@@ -1 +1,4 @@
-pub(crate) mod note;
\ No newline at end of file
+pub(crate) mod note;
+pub(crate) mod notebook;
+pub(crate) mod revision;
+pub(crate) mod search;
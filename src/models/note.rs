@@ -1,15 +1,29 @@
 // src/models/note.rs
 
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+// Serialized as-is by the JSON export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Note {
     pub id: Uuid,
     pub title: String,
     pub content: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-    // TODO: add categories in the future
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Kept at the top of the list whatever the sort order
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub favorite: bool,
+    // notebooks belong to one database, so exports leave them out
+    #[serde(skip)]
+    pub notebook_id: Option<Uuid>,
 }
 
 impl Note {
@@ -20,10 +34,54 @@ impl Note {
             content: content.to_string(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            deleted_at: None,
+            tags: parse_tags(content),
+            pinned: false,
+            favorite: false,
+            notebook_id: None,
         }
     }
 }
 
+/// Collects the `#hashtags` of a note, lowercased, sorted and without duplicates.
+/// Headings (`# Title`), issue numbers (`#42`), URL fragments and fenced code are ignored.
+pub fn parse_tags(content: &str) -> Vec<String> {
+    let is_tag_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '/');
+    let mut tags = Vec::new();
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut prev: Option<char> = None;
+        for (i, c) in line.char_indices() {
+            let starts_tag = c == '#'
+                && !prev.is_some_and(|p| p.is_alphanumeric() || matches!(p, '#' | '&' | '/'));
+            prev = Some(c);
+            if !starts_tag {
+                continue;
+            }
+            let rest = &line[i + 1..];
+            let end = rest.find(|c: char| !is_tag_char(c)).unwrap_or(rest.len());
+            let tag = rest[..end].trim_end_matches(['-', '/']);
+            if tag.chars().any(char::is_alphabetic) {
+                tags.push(tag.to_lowercase());
+            }
+        }
+    }
+
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,5 +94,21 @@ mod tests {
         assert!(!note.id.is_nil());
         assert!(note.created_at <= Utc::now());
         assert!(note.updated_at <= Utc::now());
+        assert!(note.tags.is_empty());
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        let content = "# Heading\n\
+                       Plan for #Work and #home-office, see #42.\n\
+                       https://example.com/page#anchor #work again\n\
+                       ```\n\
+                       #include <stdio.h>\n\
+                       ```\n\
+                       #todo/later";
+        assert_eq!(
+            parse_tags(content),
+            vec!["home-office", "todo/later", "work"]
+        );
     }
 }
@@ -0,0 +1,145 @@
+// src/models/notebook.rs
+
+use uuid::Uuid;
+
+/// A folder of notes, nested inside `parent_id` or at the top level
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notebook {
+    pub id: Uuid,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+}
+
+impl Notebook {
+    pub fn new(name: &str, parent_id: Option<Uuid>) -> Self {
+        Notebook {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            parent_id,
+        }
+    }
+}
+
+/// A notebook in its place in the tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeEntry {
+    pub notebook: Notebook,
+    /// 0 for top-level notebooks
+    pub depth: usize,
+    /// Names from the top down joined by "/", e.g. "Work/Projects"
+    pub path: String,
+    /// Notes outside the trash in this notebook and the ones below it
+    pub count: usize,
+}
+
+/// The names of a "Work/Projects" style path, without empty parts
+pub fn split_path(path: &str) -> Vec<&str> {
+    path.split('/')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// The position of the notebook at `path` in a tree, matching names regardless of case
+pub fn find(entries: &[TreeEntry], path: &str) -> Option<usize> {
+    let wanted = split_path(path).join("/").to_lowercase();
+    entries.iter().position(|e| e.path.to_lowercase() == wanted)
+}
+
+/// Orders notebooks depth-first with siblings by name, given each one with the number
+/// of notes directly inside it
+pub fn tree(notebooks: Vec<(Notebook, usize)>) -> Vec<TreeEntry> {
+    let mut entries = Vec::with_capacity(notebooks.len());
+    add_children(&notebooks, None, 0, "", &mut entries);
+    // totals are summed bottom-up, so children come before their parents here
+    for i in (0..entries.len()).rev() {
+        let depth = entries[i].depth;
+        let below: usize = entries[i + 1..]
+            .iter()
+            .take_while(|e| e.depth > depth)
+            .filter(|e| e.depth == depth + 1)
+            .map(|e| e.count)
+            .sum();
+        entries[i].count += below;
+    }
+    entries
+}
+
+fn add_children(
+    notebooks: &[(Notebook, usize)],
+    parent: Option<Uuid>,
+    depth: usize,
+    prefix: &str,
+    entries: &mut Vec<TreeEntry>,
+) {
+    let mut children: Vec<&(Notebook, usize)> = notebooks
+        .iter()
+        .filter(|(n, _)| n.parent_id == parent)
+        .collect();
+    children.sort_by_key(|(n, _)| n.name.to_lowercase());
+    for (notebook, count) in children {
+        let path = format!("{}{}", prefix, notebook.name);
+        entries.push(TreeEntry {
+            notebook: notebook.clone(),
+            depth,
+            path: path.clone(),
+            count: *count,
+        });
+        add_children(
+            notebooks,
+            Some(notebook.id),
+            depth + 1,
+            &format!("{}/", path),
+            entries,
+        );
+    }
+}
+
+/// The ids of the notebook at `index` of a tree and of every notebook below it
+pub fn subtree(entries: &[TreeEntry], index: usize) -> Vec<Uuid> {
+    let depth = entries[index].depth;
+    std::iter::once(&entries[index])
+        .chain(entries[index + 1..].iter().take_while(|e| e.depth > depth))
+        .map(|e| e.notebook.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_orders_and_counts() {
+        let work = Notebook::new("work", None);
+        let home = Notebook::new("Home", None);
+        let projects = Notebook::new("Projects", Some(work.id));
+        let old = Notebook::new("Old", Some(projects.id));
+        let entries = tree(vec![
+            (old.clone(), 1),
+            (work.clone(), 2),
+            (projects.clone(), 3),
+            (home.clone(), 0),
+        ]);
+        let paths: Vec<(&str, usize, usize)> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.depth, e.count))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("Home", 0, 0),
+                ("work", 0, 6),
+                ("work/Projects", 1, 4),
+                ("work/Projects/Old", 2, 1),
+            ]
+        );
+        assert_eq!(subtree(&entries, 2), [projects.id, old.id]);
+        assert_eq!(subtree(&entries, 0), [home.id]);
+    }
+
+    #[test]
+    fn test_split_path() {
+        assert_eq!(split_path(" Work / Projects/"), ["Work", "Projects"]);
+        assert!(split_path("/").is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+// src/models/revision.rs
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A previous version of a note, numbered from 1 upwards per note
+pub struct Revision {
+    pub note_id: Uuid,
+    pub revision: u32,
+    pub title: String,
+    pub content: String,
+    pub updated_at: DateTime<Utc>,
+}
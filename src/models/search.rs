@@ -0,0 +1,55 @@
+// src/models/search.rs
+
+use crate::models::note::Note;
+
+/// Markers wrapped around matched terms inside a snippet
+pub const MATCH_START: &str = "\u{2}";
+pub const MATCH_END: &str = "\u{3}";
+
+pub struct SearchHit {
+    pub note: Note,
+    pub snippet: String,
+}
+
+impl SearchHit {
+    /// Splits the snippet into `(text, matched)` segments, dropping the markers.
+    pub fn segments(&self) -> Vec<(&str, bool)> {
+        let mut segments = Vec::new();
+        let mut rest = self.snippet.as_str();
+        while let Some(start) = rest.find(MATCH_START) {
+            if start > 0 {
+                segments.push((&rest[..start], false));
+            }
+            rest = &rest[start + MATCH_START.len()..];
+            let end = rest.find(MATCH_END).unwrap_or(rest.len());
+            segments.push((&rest[..end], true));
+            rest = rest.get(end + MATCH_END.len()..).unwrap_or("");
+        }
+        if !rest.is_empty() {
+            segments.push((rest, false));
+        }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let hit = SearchHit {
+            note: Note::new("t", "c"),
+            snippet: format!("buy {MATCH_START}milk{MATCH_END} and {MATCH_START}eggs{MATCH_END}"),
+        };
+        assert_eq!(
+            hit.segments(),
+            vec![
+                ("buy ", false),
+                ("milk", true),
+                (" and ", false),
+                ("eggs", true)
+            ]
+        );
+    }
+}
@@ -0,0 +1,578 @@
+// src/text_buffer.rs
+
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+/// Editable text with a cursor. The cursor is a byte offset that always sits on a
+/// char boundary; lines are the `\n`-separated parts of the text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextBuffer {
+    text: String,
+    cursor: usize,
+    // column kept while moving up and down across shorter lines
+    goal_column: Option<usize>,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    // the last edit can no longer grow, e.g. because the cursor moved away
+    sealed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditKind {
+    Insert,
+    Backspace,
+    Delete,
+    // a whole range at once, never merged with its neighbours
+    Replace,
+}
+
+/// One undoable step: `removed` was replaced by `inserted` at byte offset `pos`.
+/// Runs of typing or deleting are merged into a single step.
+#[derive(Debug, Clone, PartialEq)]
+struct Edit {
+    kind: EditKind,
+    pos: usize,
+    removed: String,
+    inserted: String,
+    cursor_before: usize,
+    cursor_after: usize,
+}
+
+#[derive(PartialEq)]
+enum CharClass {
+    Space,
+    Word,
+    Punctuation,
+}
+
+fn class_of(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Space
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+impl TextBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Empties the buffer and forgets its undo history
+    pub fn clear(&mut self) {
+        self.set_text(String::new());
+    }
+
+    /// Replaces the whole text, puts the cursor at its end and forgets the undo history
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+        self.goal_column = None;
+        self.undo.clear();
+        self.redo.clear();
+        self.sealed = false;
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let pos = self.cursor;
+        self.text.insert(pos, c);
+        self.cursor += c.len_utf8();
+        self.goal_column = None;
+
+        // a run of typing ends at a newline or where a new word starts
+        let extends_run = self.last_open_edit(EditKind::Insert).is_some_and(|last| {
+            let starts_word = last.inserted.ends_with(char::is_whitespace) && !c.is_whitespace();
+            last.pos + last.inserted.len() == pos && c != '\n' && !starts_word
+        });
+        if extends_run {
+            let last = self.undo.last_mut().unwrap();
+            last.inserted.push(c);
+            last.cursor_after = self.cursor;
+        } else {
+            self.record(EditKind::Insert, pos, String::new(), c.to_string(), pos);
+        }
+    }
+
+    /// Deletes the char before the cursor
+    pub fn backspace(&mut self) {
+        self.goal_column = None;
+        let Some(c) = self.text[..self.cursor].chars().next_back() else {
+            return;
+        };
+        let before = self.cursor;
+        self.cursor -= c.len_utf8();
+        self.text.remove(self.cursor);
+
+        let extends_run = self
+            .last_open_edit(EditKind::Backspace)
+            .is_some_and(|last| last.pos == before);
+        if extends_run {
+            let last = self.undo.last_mut().unwrap();
+            last.removed.insert(0, c);
+            last.pos = self.cursor;
+            last.cursor_after = self.cursor;
+        } else {
+            self.record(
+                EditKind::Backspace,
+                self.cursor,
+                c.to_string(),
+                String::new(),
+                before,
+            );
+        }
+    }
+
+    /// Deletes the char under the cursor
+    pub fn delete(&mut self) {
+        self.goal_column = None;
+        if self.cursor >= self.text.len() {
+            return;
+        }
+        let c = self.text.remove(self.cursor);
+
+        let extends_run = self
+            .last_open_edit(EditKind::Delete)
+            .is_some_and(|last| last.pos == self.cursor);
+        if extends_run {
+            self.undo.last_mut().unwrap().removed.push(c);
+        } else {
+            self.record(
+                EditKind::Delete,
+                self.cursor,
+                c.to_string(),
+                String::new(),
+                self.cursor,
+            );
+        }
+    }
+
+    /// Replaces the text in `range` with `text` as one undo step and puts the cursor after it
+    pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        let before = self.cursor;
+        let removed = self.text[range.clone()].to_string();
+        self.text.replace_range(range.clone(), text);
+        self.cursor = range.start + text.len();
+        self.goal_column = None;
+        self.record(
+            EditKind::Replace,
+            range.start,
+            removed,
+            text.to_string(),
+            before,
+        );
+    }
+
+    /// Reverts the last edit; false when there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo.pop() else {
+            return false;
+        };
+        self.text
+            .replace_range(edit.pos..edit.pos + edit.inserted.len(), &edit.removed);
+        self.cursor = edit.cursor_before;
+        self.goal_column = None;
+        self.sealed = true;
+        self.redo.push(edit);
+        true
+    }
+
+    /// Applies the last undone edit again; false when there is nothing to redo
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        self.text
+            .replace_range(edit.pos..edit.pos + edit.removed.len(), &edit.inserted);
+        self.cursor = edit.cursor_after;
+        self.goal_column = None;
+        self.sealed = true;
+        self.undo.push(edit);
+        true
+    }
+
+    // The last edit if it is of `kind` and may still grow
+    fn last_open_edit(&self, kind: EditKind) -> Option<&Edit> {
+        self.undo
+            .last()
+            .filter(|last| !self.sealed && last.kind == kind)
+    }
+
+    fn record(
+        &mut self,
+        kind: EditKind,
+        pos: usize,
+        removed: String,
+        inserted: String,
+        cursor_before: usize,
+    ) {
+        self.undo.push(Edit {
+            kind,
+            pos,
+            removed,
+            inserted,
+            cursor_before,
+            cursor_after: self.cursor,
+        });
+        self.redo.clear();
+        self.sealed = false;
+    }
+
+    /// Moves to byte offset `pos`, or the char boundary before it
+    pub fn set_cursor(&mut self, pos: usize) {
+        let mut pos = pos.min(self.text.len());
+        while !self.text.is_char_boundary(pos) {
+            pos -= 1;
+        }
+        self.cursor = pos;
+        self.goal_column = None;
+        self.sealed = true;
+    }
+
+    /// Steps back onto the last char when the cursor is past the end of a non-empty line,
+    /// keeping the column remembered for vertical moves
+    pub fn clamp_to_line(&mut self) {
+        if self.cursor == self.line_end(self.cursor)
+            && let Some(c) = self.text[self.line_start(self.cursor)..self.cursor]
+                .chars()
+                .next_back()
+        {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+        self.goal_column = None;
+        self.sealed = true;
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+        self.goal_column = None;
+        self.sealed = true;
+    }
+
+    /// Moves to the start of the previous word
+    pub fn move_word_left(&mut self) {
+        let mut chars = self.text[..self.cursor].char_indices().rev().peekable();
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        if let Some(&(_, first)) = chars.peek() {
+            let class = class_of(first);
+            while chars.next_if(|(_, c)| class_of(*c) == class).is_some() {}
+        }
+        self.cursor = chars.peek().map_or(0, |(i, c)| i + c.len_utf8());
+        self.goal_column = None;
+        self.sealed = true;
+    }
+
+    /// Moves to the start of the next word
+    pub fn move_word_right(&mut self) {
+        let mut chars = self.text[self.cursor..].char_indices().peekable();
+        if let Some(&(_, first)) = chars.peek() {
+            let class = class_of(first);
+            if class != CharClass::Space {
+                while chars.next_if(|(_, c)| class_of(*c) == class).is_some() {}
+            }
+        }
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        self.cursor += chars
+            .peek()
+            .map_or(self.text.len() - self.cursor, |(i, _)| *i);
+        self.goal_column = None;
+        self.sealed = true;
+    }
+
+    /// Moves to the end of the word, or of the next one when already there
+    pub fn move_word_end(&mut self) {
+        let mut chars = self.text[self.cursor..].char_indices().skip(1).peekable();
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        if let Some(&(i, first)) = chars.peek() {
+            let class = class_of(first);
+            let mut last = i;
+            while let Some((i, _)) = chars.next_if(|(_, c)| class_of(*c) == class) {
+                last = i;
+            }
+            self.cursor += last;
+        }
+        self.goal_column = None;
+        self.sealed = true;
+    }
+
+    pub fn move_line_start(&mut self) {
+        self.cursor = self.line_start(self.cursor);
+        self.goal_column = None;
+        self.sealed = true;
+    }
+
+    pub fn move_line_end(&mut self) {
+        self.cursor = self.line_end(self.cursor);
+        self.goal_column = None;
+        self.sealed = true;
+    }
+
+    /// Moves `lines` lines up, keeping the column where possible
+    pub fn move_up(&mut self, lines: usize) {
+        let column = self.goal_column.unwrap_or_else(|| self.column());
+        let mut start = self.line_start(self.cursor);
+        for _ in 0..lines {
+            if start == 0 {
+                break;
+            }
+            start = self.line_start(start - 1);
+        }
+        self.cursor = self.offset_in_line(start, column);
+        self.goal_column = Some(column);
+        self.sealed = true;
+    }
+
+    /// Moves `lines` lines down, keeping the column where possible
+    pub fn move_down(&mut self, lines: usize) {
+        let column = self.goal_column.unwrap_or_else(|| self.column());
+        let mut start = self.line_start(self.cursor);
+        for _ in 0..lines {
+            let end = self.line_end(start);
+            if end == self.text.len() {
+                break;
+            }
+            start = end + 1;
+        }
+        self.cursor = self.offset_in_line(start, column);
+        self.goal_column = Some(column);
+        self.sealed = true;
+    }
+
+    /// Zero-based line and column (in chars) of the cursor
+    pub fn line_col(&self) -> (usize, usize) {
+        let line = self.text[..self.cursor].matches('\n').count();
+        (line, self.column())
+    }
+
+    fn column(&self) -> usize {
+        self.text[self.line_start(self.cursor)..self.cursor]
+            .chars()
+            .count()
+    }
+
+    /// Byte offset where the line holding `pos` starts
+    pub fn line_start(&self, pos: usize) -> usize {
+        self.text[..pos].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// Byte offset of the newline ending the line holding `pos`, or the text's end
+    pub fn line_end(&self, pos: usize) -> usize {
+        self.text[pos..]
+            .find('\n')
+            .map_or(self.text.len(), |i| pos + i)
+    }
+
+    // Byte offset of `column` chars into the line starting at `start`, clamped to its end
+    fn offset_in_line(&self, start: usize, column: usize) -> usize {
+        let end = self.line_end(start);
+        self.text[start..end]
+            .char_indices()
+            .nth(column)
+            .map_or(end, |(i, _)| start + i)
+    }
+}
+
+/// Splits `text` into rows no wider than `width` columns, breaking at any char.
+/// Each row is the byte range it covers; newlines end a row and belong to none.
+pub fn wrap_rows(text: &str, width: usize) -> Vec<(usize, usize)> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        if c == '\n' {
+            rows.push((start, i));
+            start = i + 1;
+            used = 0;
+            continue;
+        }
+        let w = c.width().unwrap_or(0);
+        if used + w > width && i > start {
+            rows.push((start, i));
+            start = i;
+            used = 0;
+        }
+        used += w;
+    }
+    rows.push((start, text.len()));
+    rows
+}
+
+/// Row and display column of byte offset `pos` within the rows from `wrap_rows`
+pub fn locate(text: &str, rows: &[(usize, usize)], pos: usize, width: usize) -> (usize, usize) {
+    // the last row starting at or before `pos`; a wrapped row's end is the next one's start
+    let row = rows
+        .iter()
+        .rposition(|&(start, _)| start <= pos)
+        .unwrap_or(0);
+    let (start, end) = rows[row];
+    let column: usize = text[start..pos.min(end)]
+        .chars()
+        .map(|c| c.width().unwrap_or(0))
+        .sum();
+    // a full row puts the cursor at the start of the next visual row
+    if column >= width.max(1) {
+        (row + 1, 0)
+    } else {
+        (row, column)
+    }
+}
+
+/// Byte offset shown at display `column` of `row` in the rows from `wrap_rows`, the
+/// inverse of `locate`; positions past a row's end land on its end
+pub fn offset_at(text: &str, rows: &[(usize, usize)], row: usize, column: usize) -> usize {
+    let Some(&(start, end)) = rows.get(row).or(rows.last()) else {
+        return 0;
+    };
+    if row >= rows.len() {
+        return end;
+    }
+    let mut used = 0;
+    for (i, c) in text[start..end].char_indices() {
+        let w = c.width().unwrap_or(0);
+        // a click on the right half of a wide char still lands on it
+        if used + w > column {
+            return start + i;
+        }
+        used += w;
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(text: &str, cursor: usize) -> TextBuffer {
+        let mut b = TextBuffer::new();
+        b.set_text(text);
+        b.cursor = cursor;
+        b
+    }
+
+    #[test]
+    fn test_insert_and_delete_at_cursor() {
+        let mut b = buffer("helo", 3);
+        b.insert_char('l');
+        assert_eq!((b.as_str(), b.cursor()), ("hello", 4));
+        b.backspace();
+        b.backspace();
+        assert_eq!((b.as_str(), b.cursor()), ("heo", 2));
+        b.delete();
+        assert_eq!(b.as_str(), "he");
+        b.delete();
+        assert_eq!(b.as_str(), "he");
+    }
+
+    #[test]
+    fn test_multibyte_chars() {
+        let mut b = buffer("çağ", 0);
+        b.move_right();
+        b.move_right();
+        assert_eq!(b.cursor(), "ça".len());
+        b.backspace();
+        assert_eq!(b.as_str(), "çğ");
+        b.move_left();
+        b.move_left();
+        assert_eq!(b.cursor(), 0);
+    }
+
+    #[test]
+    fn test_home_end_and_vertical_moves() {
+        let mut b = buffer("first line\nab\nthird line", 7);
+        b.move_down(1);
+        assert_eq!(b.line_col(), (1, 2));
+        // the column is remembered across the short line
+        b.move_down(1);
+        assert_eq!(b.line_col(), (2, 7));
+        b.move_up(5);
+        assert_eq!(b.line_col(), (0, 7));
+        b.move_line_end();
+        assert_eq!(b.line_col(), (0, 10));
+        b.move_down(10);
+        assert_eq!(b.line_col(), (2, 10));
+        b.move_line_start();
+        assert_eq!(b.line_col(), (2, 0));
+    }
+
+    #[test]
+    fn test_word_motions() {
+        let text = "let x = foo_bar(1);  next";
+        let mut b = buffer(text, 0);
+        let mut stops = Vec::new();
+        for _ in 0..8 {
+            b.move_word_right();
+            stops.push(b.cursor());
+        }
+        assert_eq!(stops, vec![4, 6, 8, 15, 16, 17, 21, 25]);
+
+        let mut stops = Vec::new();
+        for _ in 0..8 {
+            b.move_word_left();
+            stops.push(b.cursor());
+        }
+        assert_eq!(stops, vec![21, 17, 16, 15, 8, 6, 4, 0]);
+
+        let mut stops = Vec::new();
+        for _ in 0..6 {
+            b.move_word_end();
+            stops.push(b.cursor());
+        }
+        assert_eq!(stops, vec![2, 4, 6, 14, 15, 16]);
+    }
+
+    #[test]
+    fn test_replace_is_one_undo_step() {
+        let mut b = buffer("one\ntwo\nthree", 2);
+        b.replace(4..8, "");
+        assert_eq!((b.as_str(), b.cursor()), ("one\nthree", 4));
+        b.replace(0..0, "zero\n");
+        b.undo();
+        b.undo();
+        assert_eq!((b.as_str(), b.cursor()), ("one\ntwo\nthree", 2));
+
+        b.set_cursor(7);
+        b.clamp_to_line();
+        assert_eq!(b.cursor(), 6);
+    }
+
+    #[test]
+    fn test_wrap_rows_and_locate() {
+        let text = "abcdef\ngh";
+        let rows = wrap_rows(text, 4);
+        assert_eq!(rows, vec![(0, 4), (4, 6), (7, 9)]);
+        assert_eq!(locate(text, &rows, 0, 4), (0, 0));
+        assert_eq!(locate(text, &rows, 4, 4), (1, 0));
+        assert_eq!(locate(text, &rows, 6, 4), (1, 2));
+        assert_eq!(locate(text, &rows, 9, 4), (2, 2));
+
+        // a line that exactly fills the width moves the cursor to the next row
+        let rows = wrap_rows("abcd", 4);
+        assert_eq!(locate("abcd", &rows, 4, 4), (1, 0));
+        // wide chars count double
+        assert_eq!(wrap_rows("日本語", 4), vec![(0, 6), (6, 9)]);
+
+        let rows = wrap_rows(text, 4);
+        assert_eq!(offset_at(text, &rows, 1, 1), 5);
+        assert_eq!(offset_at(text, &rows, 2, 9), 9);
+        assert_eq!(offset_at(text, &rows, 7, 0), 9);
+        assert_eq!(offset_at("日本語", &wrap_rows("日本語", 4), 0, 3), 3);
+    }
+}
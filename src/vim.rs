@@ -0,0 +1,629 @@
+// src/vim.rs
+
+use crate::input::Action;
+use crate::text_buffer::TextBuffer;
+use std::fmt;
+use std::mem;
+use std::ops::Range;
+
+/// Largest count a command takes, so a long run of digits cannot exhaust memory or time
+const MAX_COUNT: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimMode {
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+impl fmt::Display for VimMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VimMode::Normal => "NORMAL",
+            VimMode::Insert => "INSERT",
+            VimMode::Visual => "VISUAL",
+            VimMode::VisualLine => "VISUAL LINE",
+        })
+    }
+}
+
+// What a key did in normal mode, which decides whether `.` can repeat it
+enum Step {
+    // a count or operator that waits for more keys
+    Pending,
+    Motion,
+    Change,
+    // a change that goes on in insert mode until Esc
+    Insert,
+}
+
+/// Modal editing on top of a `TextBuffer`: motions, `d`/`y` operators, counts and `.`
+pub struct Vim {
+    mode: VimMode,
+    count: Option<usize>,
+    // `d` or `y` waiting for its motion, with the count typed before it
+    operator: Option<(char, Option<usize>)>,
+    // where a visual selection started
+    anchor: usize,
+    register: String,
+    linewise: bool,
+    // keys of the change being typed, and of the last complete one
+    keys: Vec<Action>,
+    last_change: Vec<Action>,
+    replaying: bool,
+}
+
+impl Vim {
+    pub fn new() -> Self {
+        Self {
+            mode: VimMode::Normal,
+            count: None,
+            operator: None,
+            anchor: 0,
+            register: String::new(),
+            linewise: false,
+            keys: Vec::new(),
+            last_change: Vec::new(),
+            replaying: false,
+        }
+    }
+
+    pub fn mode(&self) -> VimMode {
+        self.mode
+    }
+
+    /// Back to normal mode for a new text; the register and `.` survive
+    pub fn reset(&mut self) {
+        self.mode = VimMode::Normal;
+        self.count = None;
+        self.operator = None;
+        self.keys.clear();
+    }
+
+    /// The count and operator typed so far, e.g. "2d"
+    pub fn pending(&self) -> String {
+        let mut pending = String::new();
+        if let Some((op, count)) = self.operator {
+            pending.extend(count.map(|n| n.to_string()));
+            pending.push(op);
+        }
+        pending.extend(self.count.map(|n| n.to_string()));
+        pending
+    }
+
+    /// True while Esc means something to vim rather than leaving the editor
+    pub fn captures_esc(&self) -> bool {
+        self.mode != VimMode::Normal || self.count.is_some() || self.operator.is_some()
+    }
+
+    /// The selected byte range in visual modes
+    pub fn selection(&self, buffer: &TextBuffer) -> Option<Range<usize>> {
+        let (from, to) = ordered(self.anchor, buffer.cursor());
+        match self.mode {
+            VimMode::Visual => Some(from..next_char(buffer.as_str(), to).min(buffer.line_end(to))),
+            VimMode::VisualLine => Some(buffer.line_start(from)..buffer.line_end(to)),
+            _ => None,
+        }
+    }
+
+    /// Applies one key; `single_line` keeps newlines out, e.g. of a title
+    pub fn handle(&mut self, action: &Action, buffer: &mut TextBuffer, single_line: bool) {
+        if !self.replaying {
+            self.keys.push(*action);
+        }
+        match self.mode {
+            VimMode::Insert => {
+                if self.insert(action, buffer, single_line) {
+                    self.finish_change();
+                }
+            }
+            VimMode::Normal => match self.normal(action, buffer, single_line) {
+                Step::Pending | Step::Insert => {}
+                Step::Change => self.finish_change(),
+                Step::Motion => {
+                    if !self.replaying {
+                        self.keys.clear();
+                    }
+                }
+            },
+            VimMode::Visual | VimMode::VisualLine => {
+                self.visual(action, buffer);
+                if !self.replaying {
+                    self.keys.clear();
+                }
+            }
+        }
+    }
+
+    fn finish_change(&mut self) {
+        if !self.replaying {
+            self.last_change = mem::take(&mut self.keys);
+        }
+    }
+
+    // Returns true when Esc ends the insert
+    fn insert(&mut self, action: &Action, buffer: &mut TextBuffer, single_line: bool) -> bool {
+        match action {
+            Action::Esc => {
+                if buffer.cursor() > buffer.line_start(buffer.cursor()) {
+                    buffer.move_left();
+                }
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            Action::Char(c) => buffer.insert_char(*c),
+            Action::Enter if !single_line => buffer.insert_char('\n'),
+            Action::Backspace => buffer.backspace(),
+            Action::Delete => buffer.delete(),
+            Action::Left => buffer.move_left(),
+            Action::Right => buffer.move_right(),
+            Action::WordLeft => buffer.move_word_left(),
+            Action::WordRight => buffer.move_word_right(),
+            Action::Home => buffer.move_line_start(),
+            Action::End => buffer.move_line_end(),
+            Action::Up => buffer.move_up(1),
+            Action::Down => buffer.move_down(1),
+            _ => {}
+        }
+        false
+    }
+
+    fn normal(&mut self, action: &Action, buffer: &mut TextBuffer, single_line: bool) -> Step {
+        if *action == Action::Esc {
+            self.count = None;
+            self.operator = None;
+            return Step::Motion;
+        }
+        let Some(key) = key(action) else {
+            return Step::Motion;
+        };
+
+        if let Some(digit) = key.to_digit(10)
+            && (digit > 0 || self.count.is_some())
+        {
+            let count = self.count.unwrap_or(0).saturating_mul(10);
+            self.count = Some(count.saturating_add(digit as usize).min(MAX_COUNT));
+            return Step::Pending;
+        }
+        let count = self.count.take();
+
+        if let Some((op, op_count)) = self.operator.take() {
+            let n = op_count
+                .unwrap_or(1)
+                .saturating_mul(count.unwrap_or(1))
+                .min(MAX_COUNT);
+            return self.operate(op, key, n, buffer);
+        }
+        let n = count.unwrap_or(1);
+
+        let step = match key {
+            'd' | 'y' => {
+                self.operator = Some((key, count));
+                return Step::Pending;
+            }
+            'x' => {
+                let start = buffer.cursor();
+                let end = nth_char(buffer.as_str(), start, n).min(buffer.line_end(start));
+                if start == end {
+                    return Step::Motion;
+                }
+                self.cut(buffer, start..end);
+                Step::Change
+            }
+            'p' | 'P' if !self.register.is_empty() => {
+                self.paste(buffer, key == 'p', n, single_line);
+                Step::Change
+            }
+            'o' | 'O' if !single_line => {
+                let pos = if key == 'o' {
+                    buffer.line_end(buffer.cursor())
+                } else {
+                    buffer.line_start(buffer.cursor())
+                };
+                buffer.replace(pos..pos, "\n");
+                buffer.set_cursor(if key == 'o' { pos + 1 } else { pos });
+                self.mode = VimMode::Insert;
+                return Step::Insert;
+            }
+            'i' | 'a' | 'I' | 'A' => {
+                match key {
+                    'a' if buffer.cursor() < buffer.line_end(buffer.cursor()) => {
+                        buffer.move_right()
+                    }
+                    'I' => buffer.set_cursor(first_non_blank(buffer, buffer.cursor())),
+                    'A' => buffer.move_line_end(),
+                    _ => {}
+                }
+                self.mode = VimMode::Insert;
+                return Step::Insert;
+            }
+            'v' | 'V' => {
+                self.anchor = buffer.cursor();
+                self.mode = if key == 'v' {
+                    VimMode::Visual
+                } else {
+                    VimMode::VisualLine
+                };
+                Step::Motion
+            }
+            'u' => {
+                for _ in 0..n {
+                    buffer.undo();
+                }
+                Step::Motion
+            }
+            '.' => {
+                self.repeat(count, buffer, single_line);
+                return Step::Motion;
+            }
+            _ => {
+                motion(key, n, buffer);
+                Step::Motion
+            }
+        };
+        buffer.clamp_to_line();
+        step
+    }
+
+    // `d` or `y` over `n` times the motion `key`; `dd` and `yy` take whole lines
+    fn operate(&mut self, op: char, key: char, n: usize, buffer: &mut TextBuffer) -> Step {
+        let start = buffer.cursor();
+        let (range, linewise) = if key == op {
+            let mut end = start;
+            for _ in 1..n {
+                end = (buffer.line_end(end) + 1).min(buffer.as_str().len());
+            }
+            (start..end, true)
+        } else if matches!(key, 'j' | 'k') {
+            motion(key, n, buffer);
+            let (from, to) = ordered(start, buffer.cursor());
+            (from..to, true)
+        } else if motion(key, n, buffer) {
+            let (from, mut to) = ordered(start, buffer.cursor());
+            match key {
+                'e' => to = next_char(buffer.as_str(), to),
+                // `dw` on the last word of a line stops at its end
+                'w' if buffer.as_str()[from..to].contains('\n') => {
+                    to = buffer.line_end(from).max(from)
+                }
+                _ => {}
+            }
+            (from..to, false)
+        } else {
+            return Step::Motion;
+        };
+
+        if op == 'y' {
+            if linewise {
+                self.register = format!(
+                    "{}\n",
+                    &buffer.as_str()[buffer.line_start(range.start)..buffer.line_end(range.end)]
+                );
+                self.linewise = true;
+            } else {
+                self.register = buffer.as_str()[range.clone()].to_string();
+                self.linewise = false;
+            }
+            buffer.set_cursor(if linewise { start } else { range.start });
+            buffer.clamp_to_line();
+            return Step::Motion;
+        }
+        if linewise {
+            self.cut_lines(buffer, range.start, range.end);
+        } else {
+            self.cut(buffer, range);
+        }
+        buffer.clamp_to_line();
+        Step::Change
+    }
+
+    fn visual(&mut self, action: &Action, buffer: &mut TextBuffer) {
+        let Some(key) = key(action) else {
+            return;
+        };
+        match key {
+            'd' | 'x' | 'y' => {
+                let line = self.mode == VimMode::VisualLine;
+                let (from, to) = ordered(self.anchor, buffer.cursor());
+                let range = self.selection(buffer).unwrap_or(from..to);
+                if key == 'y' {
+                    self.register = buffer.as_str()[range.clone()].to_string();
+                    if line {
+                        self.register.push('\n');
+                    }
+                    self.linewise = line;
+                    buffer.set_cursor(range.start);
+                } else if line {
+                    self.cut_lines(buffer, from, to);
+                } else {
+                    self.cut(buffer, range);
+                }
+                self.mode = VimMode::Normal;
+            }
+            '\u{1b}' => self.mode = VimMode::Normal,
+            'v' | 'V' => {
+                let mode = if key == 'v' {
+                    VimMode::Visual
+                } else {
+                    VimMode::VisualLine
+                };
+                self.mode = if self.mode == mode {
+                    VimMode::Normal
+                } else {
+                    mode
+                };
+            }
+            _ => {
+                motion(key, 1, buffer);
+            }
+        }
+        buffer.clamp_to_line();
+    }
+
+    // Deletes `range` into the register
+    fn cut(&mut self, buffer: &mut TextBuffer, range: Range<usize>) {
+        self.register = buffer.as_str()[range.clone()].to_string();
+        self.linewise = false;
+        buffer.replace(range, "");
+    }
+
+    // Deletes the whole lines holding `from` through `to` into the register
+    fn cut_lines(&mut self, buffer: &mut TextBuffer, from: usize, to: usize) {
+        let start = buffer.line_start(from);
+        let end = buffer.line_end(to);
+        let len = buffer.as_str().len();
+        // take a newline along: the one after, or before the last line
+        let range = if end < len {
+            start..end + 1
+        } else {
+            start.saturating_sub(1)..end
+        };
+        self.register = format!("{}\n", &buffer.as_str()[start..end]);
+        self.linewise = true;
+        buffer.replace(range.clone(), "");
+        buffer.set_cursor(buffer.line_start(range.start.min(buffer.as_str().len())));
+    }
+
+    // `p` puts the register after the cursor (or line), `P` before it
+    fn paste(&mut self, buffer: &mut TextBuffer, after: bool, n: usize, single_line: bool) {
+        let text = self.register.repeat(n);
+        if self.linewise && !single_line {
+            let cursor = buffer.cursor();
+            let line_end = buffer.line_end(cursor);
+            if !after {
+                let start = buffer.line_start(cursor);
+                buffer.replace(start..start, &text);
+                buffer.set_cursor(start);
+            } else if line_end < buffer.as_str().len() {
+                buffer.replace(line_end + 1..line_end + 1, &text);
+                buffer.set_cursor(line_end + 1);
+            } else {
+                // after the last line: the new lines need a newline before them instead
+                buffer.replace(
+                    line_end..line_end,
+                    &format!("\n{}", &text[..text.len() - 1]),
+                );
+                buffer.set_cursor(line_end + 1);
+            }
+            return;
+        }
+        let text = if single_line {
+            text.replace('\n', " ")
+        } else {
+            text
+        };
+        let cursor = buffer.cursor();
+        let pos = if after && cursor < buffer.line_end(cursor) {
+            next_char(buffer.as_str(), cursor)
+        } else {
+            cursor
+        };
+        buffer.replace(pos..pos, &text);
+        // onto the last char put
+        buffer.move_left();
+    }
+
+    // `.`: the keys of the last change again, with a new count if one was given
+    fn repeat(&mut self, count: Option<usize>, buffer: &mut TextBuffer, single_line: bool) {
+        if self.replaying {
+            return;
+        }
+        let mut keys = self.last_change.clone();
+        if let Some(n) = count {
+            let typed = keys
+                .iter()
+                .take_while(|a| matches!(a, Action::Char(c) if c.is_ascii_digit()))
+                .count();
+            keys.splice(..typed, n.to_string().chars().map(Action::Char));
+        }
+        self.replaying = true;
+        for action in &keys {
+            self.handle(action, buffer, single_line);
+        }
+        self.replaying = false;
+    }
+}
+
+// The vim key an action stands for in normal and visual mode
+fn key(action: &Action) -> Option<char> {
+    Some(match action {
+        Action::Char(c) => *c,
+        Action::Left | Action::Backspace => 'h',
+        Action::Right => 'l',
+        Action::Up => 'k',
+        Action::Down | Action::Enter => 'j',
+        Action::WordLeft => 'b',
+        Action::WordRight => 'w',
+        Action::Home => '0',
+        Action::End => '$',
+        Action::Delete => 'x',
+        Action::Esc => '\u{1b}',
+        _ => return None,
+    })
+}
+
+// Moves the cursor by motion `key`, `n` times; false for keys that are no motion
+fn motion(key: char, n: usize, buffer: &mut TextBuffer) -> bool {
+    for _ in 0..n {
+        let cursor = buffer.cursor();
+        match key {
+            // h and l stay within the line
+            'h' if cursor > buffer.line_start(cursor) => buffer.move_left(),
+            'l' if cursor < buffer.line_end(cursor) => buffer.move_right(),
+            'h' | 'l' => {}
+            'j' => buffer.move_down(1),
+            'k' => buffer.move_up(1),
+            'w' => buffer.move_word_right(),
+            'b' => buffer.move_word_left(),
+            'e' => buffer.move_word_end(),
+            '0' => buffer.move_line_start(),
+            '^' => buffer.set_cursor(first_non_blank(buffer, cursor)),
+            '$' => buffer.move_line_end(),
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+// Byte offset after the char at `pos`
+fn next_char(text: &str, pos: usize) -> usize {
+    nth_char(text, pos, 1)
+}
+
+fn nth_char(text: &str, pos: usize, n: usize) -> usize {
+    text[pos..]
+        .char_indices()
+        .nth(n)
+        .map_or(text.len(), |(i, _)| pos + i)
+}
+
+fn first_non_blank(buffer: &TextBuffer, pos: usize) -> usize {
+    let start = buffer.line_start(pos);
+    let line = &buffer.as_str()[start..buffer.line_end(pos)];
+    start
+        + line
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(line.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editing(text: &str) -> (Vim, TextBuffer) {
+        let mut buffer = TextBuffer::new();
+        buffer.set_text(text);
+        buffer.set_cursor(0);
+        (Vim::new(), buffer)
+    }
+
+    fn keys(vim: &mut Vim, buffer: &mut TextBuffer, keys: &str) {
+        for c in keys.chars() {
+            let action = if c == '\u{1b}' {
+                Action::Esc
+            } else {
+                Action::Char(c)
+            };
+            vim.handle(&action, buffer, false);
+        }
+    }
+
+    #[test]
+    fn test_motions_and_counts() {
+        let (mut vim, mut b) = editing("one two three\nfour five\nsix");
+        keys(&mut vim, &mut b, "2w");
+        assert_eq!(b.cursor(), 8);
+        keys(&mut vim, &mut b, "e");
+        assert_eq!(b.cursor(), 12);
+        // l stops on the last char of the line
+        keys(&mut vim, &mut b, "5l");
+        assert_eq!(b.cursor(), 12);
+        keys(&mut vim, &mut b, "jb");
+        assert_eq!(b.line_col(), (1, 5));
+        keys(&mut vim, &mut b, "k0");
+        assert_eq!(b.cursor(), 0);
+        keys(&mut vim, &mut b, "12");
+        assert_eq!(vim.pending(), "12");
+        keys(&mut vim, &mut b, "\u{1b}");
+        assert_eq!(vim.pending(), "");
+    }
+
+    #[test]
+    fn test_huge_counts_are_capped() {
+        let (mut vim, mut b) = editing("ab\ncd");
+        keys(&mut vim, &mut b, "99999999999999999999");
+        assert_eq!(vim.pending(), MAX_COUNT.to_string());
+        keys(&mut vim, &mut b, "\u{1b}yl99999999999999999999p");
+        assert_eq!(b.as_str().len(), "ab\ncd".len() + MAX_COUNT);
+
+        let (mut vim, mut b) = editing("ab\ncd");
+        keys(&mut vim, &mut b, "99999999999d99999999999l");
+        assert_eq!(b.as_str(), "\ncd");
+        keys(&mut vim, &mut b, "99999999999dd99999999999u");
+        assert_eq!(b.as_str(), "ab\ncd");
+    }
+
+    #[test]
+    fn test_delete_yank_and_put() {
+        let (mut vim, mut b) = editing("a\nb\nc\nd");
+        keys(&mut vim, &mut b, "j2dd");
+        assert_eq!(b.as_str(), "a\nd");
+        assert_eq!(b.line_col(), (1, 0));
+        keys(&mut vim, &mut b, "p");
+        assert_eq!(b.as_str(), "a\nd\nb\nc");
+        keys(&mut vim, &mut b, "kyyP");
+        assert_eq!(b.as_str(), "a\nd\nd\nb\nc");
+        keys(&mut vim, &mut b, "u");
+        assert_eq!(b.as_str(), "a\nd\nb\nc");
+
+        let (mut vim, mut b) = editing("hello world");
+        keys(&mut vim, &mut b, "dw");
+        assert_eq!(b.as_str(), "world");
+        keys(&mut vim, &mut b, "3x$p");
+        assert_eq!(b.as_str(), "ldwor");
+        keys(&mut vim, &mut b, "0de");
+        assert_eq!(b.as_str(), "");
+    }
+
+    #[test]
+    fn test_insert_and_repeat() {
+        let (mut vim, mut b) = editing("x");
+        keys(&mut vim, &mut b, "ohi\u{1b}");
+        assert_eq!(vim.mode(), VimMode::Normal);
+        assert_eq!((b.as_str(), b.cursor()), ("x\nhi", 3));
+        keys(&mut vim, &mut b, "..");
+        assert_eq!(b.as_str(), "x\nhi\nhi\nhi");
+        keys(&mut vim, &mut b, "kkkOtop\u{1b}");
+        assert_eq!(b.as_str(), "top\nx\nhi\nhi\nhi");
+
+        let (mut vim, mut b) = editing("abcdefgh");
+        keys(&mut vim, &mut b, "2x.");
+        assert_eq!(b.as_str(), "efgh");
+        keys(&mut vim, &mut b, "3.");
+        assert_eq!(b.as_str(), "h");
+        keys(&mut vim, &mut b, "A!\u{1b}0.");
+        assert_eq!(b.as_str(), "h!!");
+    }
+
+    #[test]
+    fn test_visual_modes() {
+        let (mut vim, mut b) = editing("one two\nthree");
+        keys(&mut vim, &mut b, "wve");
+        assert_eq!(vim.selection(&b), Some(4..7));
+        keys(&mut vim, &mut b, "d");
+        assert_eq!(vim.mode(), VimMode::Normal);
+        assert_eq!(b.as_str(), "one \nthree");
+
+        keys(&mut vim, &mut b, "Vjy");
+        assert_eq!(vim.mode(), VimMode::Normal);
+        keys(&mut vim, &mut b, "jp");
+        assert_eq!(b.as_str(), "one \nthree\none \nthree");
+        keys(&mut vim, &mut b, "Vkd");
+        assert_eq!(b.as_str(), "one \nthree");
+    }
+}